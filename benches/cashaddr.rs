@@ -0,0 +1,95 @@
+use bitcoincash_addr::{AddressCodec, CashAddrCodec, HashType, Network};
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+
+// Deterministic pseudorandom 20-byte hashes, encoded on each known network
+fn addresses(count: usize) -> Vec<String> {
+    let networks = [Network::Main, Network::Test, Network::Regtest];
+    let mut x: u32 = 0x1234_5678;
+    (0..count)
+        .map(|i| {
+            let body: Vec<u8> = (0..20)
+                .map(|_| {
+                    x ^= x << 13;
+                    x ^= x >> 17;
+                    x ^= x << 5;
+                    x as u8
+                })
+                .collect();
+            CashAddrCodec::encode(&body, HashType::Key, networks[i % 3].clone()).unwrap()
+        })
+        .collect()
+}
+
+// Generator terms of the cashaddr BCH checksum
+const GENERATORS: [u64; 5] = [
+    0x0098_f2bc_8e61,
+    0x0079_b76d_99e2,
+    0x00f3_3e5f_b3c4,
+    0x00ae_2eab_e2a8,
+    0x001e_4f43_e470,
+];
+
+const CHARSET: &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+
+// Bitwise reference implementation, as used before the table-driven checksum
+fn reference_polymod(v: &[u8]) -> u64 {
+    let mut c: u64 = 1;
+    for d in v.iter() {
+        let c0: u8 = (c >> 35) as u8;
+        c = ((c & 0x0007_ffff_ffff) << 5) ^ u64::from(*d);
+        for (i, generator) in GENERATORS.iter().enumerate() {
+            if c0 & (1 << i) != 0 {
+                c ^= generator;
+            }
+        }
+    }
+    c ^ 1
+}
+
+// Validate the checksum of a lowercase cashaddr using the reference implementation
+fn reference_validate(addr_str: &str) -> bool {
+    let (prefix, payload_str) = match addr_str.split_once(':') {
+        Some(split) => split,
+        None => return false,
+    };
+    let mut data: Vec<u8> = prefix.bytes().map(|b| b & 0x1f).chain(Some(0)).collect();
+    for c in payload_str.bytes() {
+        match CHARSET.iter().position(|d| *d == c) {
+            Some(d) => data.push(d as u8),
+            None => return false,
+        }
+    }
+    reference_polymod(&data) == 0
+}
+
+fn bench_cashaddr(c: &mut Criterion) {
+    let addrs = addresses(1000);
+    let addr_strs: Vec<&str> = addrs.iter().map(String::as_str).collect();
+    assert!(addr_strs
+        .iter()
+        .all(|addr_str| reference_validate(addr_str)));
+
+    let mut group = c.benchmark_group("cashaddr");
+    group.throughput(Throughput::Elements(addr_strs.len() as u64));
+    group.bench_function("decode", |b| {
+        b.iter(|| {
+            for addr_str in &addr_strs {
+                black_box(CashAddrCodec::decode(black_box(addr_str)).is_ok());
+            }
+        })
+    });
+    group.bench_function("validate_reference", |b| {
+        b.iter(|| {
+            for addr_str in &addr_strs {
+                black_box(reference_validate(black_box(addr_str)));
+            }
+        })
+    });
+    group.bench_function("validate_many", |b| {
+        b.iter(|| black_box(CashAddrCodec::validate_many(black_box(&addr_strs))))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_cashaddr);
+criterion_main!(benches);
@@ -1,7 +1,22 @@
 #![no_main]
-use bitcoincash_addr::Address;
+use bitcoincash_addr::{
+    base58, Address, AddressCodec, Base58Codec, CashAddrCodec, ExtendedKey, Network, Wif,
+};
 use libfuzzer_sys::fuzz_target;
 
 fuzz_target!(|data: &[u8]| {
-    Address::decode(&hex::encode(data));
+    if let Ok(addr_str) = std::str::from_utf8(data) {
+        let _ = Address::decode(addr_str);
+        let _ = CashAddrCodec::decode(addr_str);
+        let _ = CashAddrCodec::decode_strict(addr_str);
+        let _ = CashAddrCodec::decode_lenient(addr_str);
+        let _ = CashAddrCodec::decode_infer_network(addr_str, &[Network::Other("ecash".to_string())]);
+        let _ = CashAddrCodec::locate_errors(addr_str);
+        let _ = CashAddrCodec::validate_many(&[addr_str]);
+        let _ = Base58Codec::decode(addr_str);
+        let _ = base58::decode(addr_str);
+        let _ = base58::decode_check(addr_str);
+        let _ = Wif::decode(addr_str);
+        let _ = ExtendedKey::decode(addr_str);
+    }
 });
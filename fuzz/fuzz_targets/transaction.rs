@@ -0,0 +1,20 @@
+#![no_main]
+use bitcoincash_addr::{Address, Network, ScriptKind, Transaction};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    // Raw bytes as a transaction and as a locking script
+    if let Ok(tx) = Transaction::decode(data) {
+        let _ = tx.output_addresses(Network::Main).count();
+        let _ = tx.input_addresses(Network::Main).count();
+        for output in &tx.outputs {
+            let _ = output.kind(Network::Main);
+        }
+    }
+    let _ = Address::from_locking_script(data, Network::Main);
+    let _ = ScriptKind::from_locking_script(data, Network::Main);
+
+    if let Ok(hex) = std::str::from_utf8(data) {
+        let _ = Transaction::from_hex(hex);
+    }
+});
@@ -1,4 +1,108 @@
-use std::{error::Error, fmt};
+use alloc::vec::Vec;
+use core::fmt;
+#[cfg(feature = "std")]
+use std::error::Error;
+
+use crate::{HashType, Network};
+
+/// Error concerning decoding of base58 and Base58Check strings.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Base58Error {
+    /// Unexpected character (char).
+    InvalidChar(char),
+    /// Decoded data is too short to contain a checksum (length).
+    TooShort(usize),
+    /// Checksum failed (expected, actual).
+    ChecksumFailed { expected: Vec<u8>, actual: Vec<u8> },
+    /// Decoded value overflowed the buffer sized for the string (length).
+    Overflow(usize),
+}
+
+impl fmt::Display for Base58Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Base58Error::InvalidChar(b) => write!(f, "invalid char ({})", b),
+            Base58Error::TooShort(length) => write!(f, "too short ({})", length),
+            Base58Error::ChecksumFailed { expected, actual } => write!(
+                f,
+                "invalid checksum (actual {:?} does not match expected {:?})",
+                actual, expected
+            ),
+            Base58Error::Overflow(length) => write!(f, "overflow ({})", length),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Error for Base58Error {
+    fn cause(&self) -> Option<&dyn Error> {
+        None
+    }
+    fn description(&self) -> &str {
+        match *self {
+            Base58Error::InvalidChar(_) => "invalid char",
+            Base58Error::TooShort(_) => "too short",
+            Base58Error::ChecksumFailed { .. } => "invalid checksum",
+            Base58Error::Overflow(_) => "overflow",
+        }
+    }
+}
+
+/// Error concerning encoding of base58 addresses.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum EncodingError {
+    /// Network has no base58 version byte (network).
+    UnsupportedNetwork(Network),
+    /// Hash type has no base58 version byte (hash type).
+    UnsupportedHashType(HashType),
+    /// Only 20-byte hashes can be encoded (length).
+    InvalidLength(usize),
+    /// Output buffer is too small.
+    BufferTooSmall,
+    /// Writing to the formatter failed.
+    Fmt(fmt::Error),
+}
+
+impl From<fmt::Error> for EncodingError {
+    fn from(err: fmt::Error) -> Self {
+        EncodingError::Fmt(err)
+    }
+}
+
+impl fmt::Display for EncodingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EncodingError::UnsupportedNetwork(network) => {
+                write!(f, "unsupported network ({:?})", network)
+            }
+            EncodingError::UnsupportedHashType(hash_type) => {
+                write!(f, "unsupported hash type ({:?})", hash_type)
+            }
+            EncodingError::InvalidLength(length) => write!(f, "invalid length ({})", length),
+            EncodingError::BufferTooSmall => write!(f, "buffer too small"),
+            EncodingError::Fmt(err) => write!(f, "formatting failed ({})", err),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Error for EncodingError {
+    fn cause(&self) -> Option<&dyn Error> {
+        match self {
+            EncodingError::Fmt(err) => Some(err),
+            _ => None,
+        }
+    }
+    fn description(&self) -> &str {
+        match *self {
+            EncodingError::UnsupportedNetwork(_) => "unsupported network",
+            EncodingError::UnsupportedHashType(_) => "unsupported hash type",
+            EncodingError::InvalidLength(_) => "invalid length",
+            EncodingError::BufferTooSmall => "buffer too small",
+            EncodingError::Fmt(_) => "formatting failed",
+        }
+    }
+}
 
 /// Error concerning decoding of base58 addresses.
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -13,6 +117,19 @@ pub enum DecodingError {
     InvalidVersion(u8),
 }
 
+impl From<Base58Error> for DecodingError {
+    fn from(err: Base58Error) -> Self {
+        match err {
+            Base58Error::InvalidChar(c) => DecodingError::InvalidChar(c),
+            Base58Error::TooShort(length) => DecodingError::InvalidLength(length),
+            Base58Error::ChecksumFailed { expected, actual } => {
+                DecodingError::ChecksumFailed { expected, actual }
+            }
+            Base58Error::Overflow(length) => DecodingError::InvalidLength(length),
+        }
+    }
+}
+
 impl fmt::Display for DecodingError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
@@ -28,6 +145,7 @@ impl fmt::Display for DecodingError {
     }
 }
 
+#[cfg(feature = "std")]
 impl Error for DecodingError {
     fn cause(&self) -> Option<&dyn Error> {
         None
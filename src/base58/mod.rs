@@ -1,10 +1,14 @@
 // https://github.com/rust-bitcoin/rust-bitcoin/blob/master/src/util/address.rs
 pub mod errors;
 
+use alloc::{string::String, vec, vec::Vec};
+use core::{fmt, mem};
+
 use bitcoin_hashes::{sha256d::Hash as Sha256d, Hash};
+use zeroize::Zeroizing;
 
 use crate::*;
-pub use errors::DecodingError;
+pub use errors::{Base58Error, DecodingError, EncodingError};
 
 const BASE58_CHARS: &[u8] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
 
@@ -28,19 +32,32 @@ const BASE58_DIGITS: [Option<u8>; 128] = [
     Some(55), Some(56), Some(57), None,     None,     None,     None,     None,     // 120-127
 ];
 
-fn from_base58_str(data: &str) -> Result<Vec<u8>, DecodingError> {
+// Length of the Base58Check checksum.
+const CHECKSUM_LEN: usize = 4;
+
+// Length of a base58 address payload: version byte and Hash160.
+const PAYLOAD_LEN: usize = 21;
+
+// Maximum number of base58 digits in an address, just over 25 * log_58(256).
+const MAX_ADDRESS_DIGITS: usize = 35;
+
+/// Decode a base58 string to bytes.
+pub fn decode(data: &str) -> Result<Vec<u8>, Base58Error> {
+    decode_zeroizing(data).map(|mut ret| mem::take(&mut *ret))
+}
+
+// Decode a base58 string to bytes, zeroizing every intermediate buffer so that secrets may
+// be decoded.
+pub(crate) fn decode_zeroizing(data: &str) -> Result<Zeroizing<Vec<u8>>, Base58Error> {
     // 11/15 is just over log_256(58)
-    let mut scratch = vec![0u8; 1 + data.len() * 11 / 15];
+    let mut scratch = Zeroizing::new(vec![0u8; 1 + data.len() * 11 / 15]);
     // Build in base 256
-    for d58 in data.bytes() {
+    for c in data.chars() {
         // Compute "X = X * 58 + next_digit" in base 256
-        if d58 as usize > BASE58_DIGITS.len() {
-            return Err(DecodingError::InvalidChar(d58 as char));
-        }
-        let mut carry = match BASE58_DIGITS[d58 as usize] {
-            Some(d58) => u32::from(d58),
-            None => {
-                return Err(DecodingError::InvalidChar(d58 as char));
+        let mut carry = match BASE58_DIGITS.get(c as usize) {
+            Some(Some(d58)) => u32::from(*d58),
+            _ => {
+                return Err(Base58Error::InvalidChar(c));
             }
         };
         for d256 in scratch.iter_mut().rev() {
@@ -48,64 +65,197 @@ fn from_base58_str(data: &str) -> Result<Vec<u8>, DecodingError> {
             *d256 = carry as u8;
             carry /= 256;
         }
-        assert_eq!(carry, 0);
+        // Unreachable as scratch is large enough
+        if carry != 0 {
+            return Err(Base58Error::Overflow(data.len()));
+        }
+    }
+
+    // Copy leading zeroes directly, then the rest of the string, without reallocating
+    let leading_zeroes = data.bytes().take_while(|&x| x == BASE58_CHARS[0]).count();
+    let digits = &scratch[scratch.iter().take_while(|&&x| x == 0).count()..];
+    let mut ret = Zeroizing::new(Vec::with_capacity(leading_zeroes + digits.len()));
+    ret.resize(leading_zeroes, 0);
+    ret.extend_from_slice(digits);
+    Ok(ret)
+}
+
+/// Encode bytes as a base58 string.
+pub fn encode(data: &[u8]) -> String {
+    encode_digits(data.iter().copied(), data.len())
+}
+
+/// Decode a Base58Check string, verifying and removing the trailing checksum.
+pub fn decode_check(data: &str) -> Result<Vec<u8>, Base58Error> {
+    decode_check_zeroizing(data).map(|mut ret| mem::take(&mut *ret))
+}
+
+// Decode a Base58Check string, zeroizing every intermediate buffer so that secrets may be
+// decoded.
+pub(crate) fn decode_check_zeroizing(data: &str) -> Result<Zeroizing<Vec<u8>>, Base58Error> {
+    let mut ret = decode_zeroizing(data)?;
+    let length = ret.len();
+    if length < CHECKSUM_LEN {
+        return Err(Base58Error::TooShort(length));
     }
 
-    // Copy leading zeroes directly
-    let mut ret: Vec<u8> = data
-        .bytes()
-        .take_while(|&x| x == BASE58_CHARS[0])
-        .map(|_| 0)
-        .collect();
-    // Copy rest of string
-    ret.extend(scratch.into_iter().skip_while(|&x| x == 0));
+    // Verify checksum
+    let payload_len = length - CHECKSUM_LEN;
+    let checksum_expected = &Sha256d::hash(&ret[..payload_len])[..CHECKSUM_LEN];
+    let checksum_actual = &ret[payload_len..];
+    if checksum_expected != checksum_actual {
+        return Err(Base58Error::ChecksumFailed {
+            expected: checksum_expected.to_vec(),
+            actual: checksum_actual.to_vec(),
+        });
+    }
+    ret.truncate(payload_len);
     Ok(ret)
 }
 
-fn to_base58_str(data: &[u8]) -> String {
-    let mut ret = Vec::with_capacity(data.len());
+/// Encode bytes as a Base58Check string, appending the first four bytes of their double
+/// SHA-256 as a checksum.
+pub fn encode_check(data: &[u8]) -> String {
+    let checksum = Sha256d::hash(data);
+    encode_digits(
+        data.iter().chain(&checksum[..CHECKSUM_LEN]).copied(),
+        data.len() + CHECKSUM_LEN,
+    )
+}
+
+// Encode `len` bytes as a base58 string, zeroizing the intermediate digits so that secrets
+// may be encoded.
+fn encode_digits(data: impl Iterator<Item = u8>, len: usize) -> String {
+    let mut digits = Zeroizing::new(vec![0u8; max_digits(len)]);
+    let len = to_base58_digits(data, &mut digits);
+    let mut ret = String::with_capacity(len);
+    ret.extend(digit_chars(&digits[..len]));
+    ret
+}
+
+// Maximum number of base58 digits encoding `len` bytes, just over `len` * log_58(256).
+fn max_digits(len: usize) -> usize {
+    len * 138 / 100 + 1
+}
+
+// Convert to base58 digits in little endian order, returning the number of digits.
+// `digits` must be at least just over log_58(256) times as long as `data`.
+fn to_base58_digits(data: impl Iterator<Item = u8>, digits: &mut [u8]) -> usize {
+    let mut len = 0;
 
     let mut leading_zero_count = 0;
     let mut leading_zeroes = true;
     // Build string in little endian with 0-58 in place of characters...
     for d256 in data {
-        let mut carry = *d256 as usize;
+        let mut carry = d256 as usize;
         if leading_zeroes && carry == 0 {
             leading_zero_count += 1;
         } else {
             leading_zeroes = false;
         }
 
-        for ch in ret.iter_mut() {
+        for ch in digits[..len].iter_mut() {
             let new_ch = *ch as usize * 256 + carry;
             *ch = (new_ch % 58) as u8;
             carry = new_ch / 58;
         }
         while carry > 0 {
-            ret.push((carry % 58) as u8);
+            digits[len] = (carry % 58) as u8;
+            len += 1;
             carry /= 58;
         }
     }
 
-    // ... then reverse it and convert to chars
-    for _ in 0..leading_zero_count {
-        ret.push(0);
+    // ... then pad with leading zeroes
+    for ch in digits[len..len + leading_zero_count].iter_mut() {
+        *ch = 0;
     }
+    len + leading_zero_count
+}
 
-    let out: String = ret
+// Map little endian base58 digits to characters, most significant first.
+fn digit_chars(digits: &[u8]) -> impl Iterator<Item = char> + '_ {
+    digits
         .iter()
         .rev()
         .map(|ch| BASE58_CHARS[*ch as usize] as char)
-        .collect();
+}
+
+// Build the version byte and Hash160 payload of a base58 address.
+fn address_payload(
+    raw: &[u8],
+    hash_type: &HashType,
+    network: &Network,
+) -> Result<[u8; PAYLOAD_LEN], EncodingError> {
+    // Legacy addresses can only carry a Hash160, P2SH32 has no base58 form
+    let length = raw.len();
+    if length != 20 {
+        return Err(EncodingError::InvalidLength(length));
+    }
+
+    let addr_type_byte = match (hash_type, network) {
+        (HashType::Key, Network::Main) => 0x00,
+        (HashType::Key, Network::Test) => 0x6f,
+        (HashType::Key, Network::Regtest) => 0x6f,
+        (HashType::Script, Network::Main) => 0x05,
+        (HashType::Script, Network::Test) => 0xc4,
+        (HashType::Script, Network::Regtest) => 0xc4,
+        (HashType::TokenKey, _) | (HashType::TokenScript, _) | (HashType::Unknown(_), _) => {
+            return Err(EncodingError::UnsupportedHashType(hash_type.clone()))
+        }
+        (_, network) => return Err(EncodingError::UnsupportedNetwork(network.clone())),
+    };
 
-    out
+    let mut payload = [0u8; PAYLOAD_LEN];
+    payload[0] = addr_type_byte;
+    payload[1..].copy_from_slice(raw);
+    Ok(payload)
 }
 
 /// Codec allowing the encoding and decoding of Base58 addresses.
 pub struct Base58Codec;
 
+impl Base58Codec {
+    /// Attempt to write the base58 address of the raw address bytes to `w`, without allocating.
+    pub fn encode_to_fmt<W: fmt::Write>(
+        raw: &[u8],
+        hash_type: &HashType,
+        network: &Network,
+        w: &mut W,
+    ) -> Result<(), EncodingError> {
+        let payload = address_payload(raw, hash_type, network)?;
+        let checksum = Sha256d::hash(&payload);
+
+        let mut digits = [0u8; MAX_ADDRESS_DIGITS];
+        let len = to_base58_digits(
+            payload.iter().chain(&checksum[..CHECKSUM_LEN]).copied(),
+            &mut digits,
+        );
+        for c in digit_chars(&digits[..len]) {
+            w.write_char(c)?;
+        }
+        Ok(())
+    }
+
+    /// Attempt to write the base58 address of the raw address bytes into `buf`, without
+    /// allocating. Returns the number of bytes written.
+    pub fn encode_to_slice(
+        raw: &[u8],
+        hash_type: &HashType,
+        network: &Network,
+        buf: &mut [u8],
+    ) -> Result<usize, EncodingError> {
+        let mut writer = SliceWriter::new(buf);
+        match Self::encode_to_fmt(raw, hash_type, network, &mut writer) {
+            Ok(()) => Ok(writer.len()),
+            Err(EncodingError::Fmt(_)) => Err(EncodingError::BufferTooSmall),
+            Err(err) => Err(err),
+        }
+    }
+}
+
 impl AddressCodec for Base58Codec {
-    type EncodingError = ();
+    type EncodingError = EncodingError;
     type DecodingError = DecodingError;
 
     fn encode(
@@ -113,34 +263,25 @@ impl AddressCodec for Base58Codec {
         hash_type: HashType,
         network: Network,
     ) -> Result<String, Self::EncodingError> {
-        let addr_type_byte = match (hash_type, network) {
-            (HashType::Key, Network::Main) => 0x00,
-            (HashType::Key, Network::Test) => 0x6f,
-            (HashType::Key, Network::Regtest) => 0x6f,
-            (HashType::Script, Network::Main) => 0x05,
-            (HashType::Script, Network::Test) => 0xc4,
-            (HashType::Script, Network::Regtest) => 0xc4,
-        };
-
-        let mut body = Vec::with_capacity(raw.len() + 5);
-        body.push(addr_type_byte);
-        body.extend(raw);
-
-        let checksum = Sha256d::hash(&body);
-        body.extend(&checksum[0..4]);
-        Ok(to_base58_str(&body))
+        let payload = address_payload(raw, &hash_type, &network)?;
+        Ok(encode_check(&payload))
     }
 
     fn decode(addr_str: &str) -> Result<Address, Self::DecodingError> {
-        // Convert from base58
-        let raw = from_base58_str(addr_str)?;
-        let length = raw.len();
-        if length != 25 {
-            return Err(DecodingError::InvalidLength(length));
+        // Reject overlong strings before the quadratic decoding
+        if addr_str.len() > MAX_ADDRESS_DIGITS {
+            return Err(DecodingError::InvalidLength(addr_str.len()));
+        }
+
+        // Convert from base58 and verify checksum
+        let payload = decode_check(addr_str)?;
+        let length = payload.len();
+        if length != PAYLOAD_LEN {
+            return Err(DecodingError::InvalidLength(length + CHECKSUM_LEN));
         }
 
         // Parse network and hash type
-        let version_byte = raw[0];
+        let version_byte = payload[0];
         let (network, hash_type) = match version_byte {
             0x00 => (Network::Main, HashType::Key),
             0x05 => (Network::Main, HashType::Script),
@@ -149,17 +290,6 @@ impl AddressCodec for Base58Codec {
             _ => return Err(DecodingError::InvalidVersion(version_byte)),
         };
 
-        // Verify checksum
-        let payload = &raw[0..raw.len() - 4];
-        let checksum_actual = &raw[raw.len() - 4..];
-        let checksum_expected = &Sha256d::hash(payload)[0..4];
-        if checksum_expected != checksum_actual {
-            return Err(DecodingError::ChecksumFailed {
-                expected: checksum_expected.to_vec(),
-                actual: checksum_actual.to_vec(),
-            });
-        }
-
         // Extract hash160 address and return
         let body = payload[1..].to_vec();
         Ok(Address {
@@ -174,6 +304,7 @@ impl AddressCodec for Base58Codec {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use alloc::string::ToString;
     use bitcoin_hashes::hash160::Hash as Hash160;
     use hex;
 
@@ -197,5 +328,124 @@ mod tests {
     fn from_legacyaddr_errors() {
         assert!(Base58Codec::decode("0").is_err());
         assert!(Base58Codec::decode("1000000000000000000000000000000000").is_err());
+
+        let overlong = "z".repeat(100_000);
+        assert_eq!(
+            Base58Codec::decode(&overlong),
+            Err(DecodingError::InvalidLength(100_000))
+        );
+        assert!(Address::decode(&overlong).is_err());
+    }
+
+    #[test]
+    fn to_legacyaddr_buffers() {
+        let pubkeyhash = hex::decode("ea2407829a5055466b27784cde8cf463167946bf").unwrap();
+        let expected = "1NM2HFXin4cEQRBLjkNZAS98qLX9JKzjKn";
+
+        let mut buf = [0u8; 34];
+        let len =
+            Base58Codec::encode_to_slice(&pubkeyhash, &HashType::Key, &Network::Main, &mut buf)
+                .unwrap();
+        assert_eq!(&buf[..len], expected.as_bytes());
+        assert_eq!(
+            Base58Codec::encode_to_slice(
+                &pubkeyhash,
+                &HashType::Key,
+                &Network::Main,
+                &mut buf[..33]
+            ),
+            Err(EncodingError::BufferTooSmall)
+        );
+
+        let address = Address::new(pubkeyhash, Scheme::Base58, HashType::Key, Network::Main);
+        assert_eq!(address.to_string(), expected);
+
+        // Leading zero bytes are encoded as leading ones
+        let zeroes = Address::new(vec![0; 20], Scheme::Base58, HashType::Key, Network::Main);
+        assert_eq!(zeroes.to_string(), "1111111111111111111114oLvT2");
+    }
+
+    #[test]
+    fn to_legacyaddr_p2sh32() {
+        let address = Address::from_redeem_script_p2sh32(&[0x51], Network::Main);
+        assert_eq!(
+            Base58Codec::encode(address.as_body(), HashType::Script, Network::Main),
+            Err(EncodingError::InvalidLength(32))
+        );
+    }
+
+    #[test]
+    fn to_legacyaddr_token_aware() {
+        let pubkeyhash = hex::decode("ea2407829a5055466b27784cde8cf463167946bf").unwrap();
+        assert_eq!(
+            Base58Codec::encode(&pubkeyhash, HashType::TokenKey, Network::Main),
+            Err(EncodingError::UnsupportedHashType(HashType::TokenKey))
+        );
+    }
+
+    #[test]
+    fn from_legacyaddr_short_inputs() {
+        let alphabet = ['1', '2', 'z', '0', 'l', '\u{7f}', '\u{80}', 'é', ' '];
+        for input in crate::short_strings(&alphabet, 4).iter() {
+            assert!(Base58Codec::decode(input).is_err());
+        }
+    }
+
+    #[test]
+    fn from_legacyaddr_non_ascii() {
+        // Characters just past the end of the lookup table, and multi-byte characters
+        assert_eq!(
+            Base58Codec::decode("1NM2HFXin4cEQRBLjkNZAS98qLX9JKzjK\u{80}"),
+            Err(DecodingError::InvalidChar('\u{80}'))
+        );
+        assert_eq!(
+            Base58Codec::decode("1NM2HFXin4cEQRBLjkNZAS98qLX9JKzjKé"),
+            Err(DecodingError::InvalidChar('é'))
+        );
+        assert_eq!(
+            Base58Codec::decode("\u{7f}"),
+            Err(DecodingError::InvalidChar('\u{7f}'))
+        );
+    }
+
+    #[test]
+    fn raw_base58() {
+        let data = hex::decode("00000102ff").unwrap();
+        let encoded = encode(&data);
+        assert_eq!(encoded, "11LiA");
+        assert_eq!(decode(&encoded).unwrap(), data);
+        assert_eq!(encode(&[]), "");
+        assert_eq!(decode("").unwrap(), Vec::<u8>::new());
+        assert_eq!(decode("1I"), Err(Base58Error::InvalidChar('I')));
+    }
+
+    #[test]
+    fn base58_check() {
+        let payload = hex::decode("00ea2407829a5055466b27784cde8cf463167946bf").unwrap();
+        let encoded = encode_check(&payload);
+        assert_eq!(encoded, "1NM2HFXin4cEQRBLjkNZAS98qLX9JKzjKn");
+        assert_eq!(decode_check(&encoded).unwrap(), payload);
+
+        // Arbitrary lengths, including payloads shorter than the checksum
+        for len in 0..80 {
+            let data: Vec<u8> = (0..len).map(|i| (i * 37) as u8).collect();
+            assert_eq!(decode_check(&encode_check(&data)).unwrap(), data);
+        }
+
+        assert_eq!(decode_check("1"), Err(Base58Error::TooShort(1)));
+        match decode_check("1NM2HFXin4cEQRBLjkNZAS98qLX9JKzjKo") {
+            Err(Base58Error::ChecksumFailed { .. }) => {}
+            result => panic!("unexpected result {:?}", result),
+        }
+    }
+
+    #[test]
+    fn to_legacyaddr_other_network() {
+        let pubkeyhash = hex::decode("ea2407829a5055466b27784cde8cf463167946bf").unwrap();
+        let network = Network::Other("ecash".to_string());
+        assert_eq!(
+            Base58Codec::encode(&pubkeyhash, HashType::Key, network.clone()),
+            Err(EncodingError::UnsupportedNetwork(network))
+        );
     }
 }
@@ -0,0 +1,281 @@
+use bitcoin_hashes::{
+    hash160::Hash as Hash160,
+    hmac::{Hmac, HmacEngine},
+    sha512::Hash as Sha512,
+    Hash, HashEngine,
+};
+use secp256k1::{PublicKey, Scalar, Secp256k1, SecretKey, Verification};
+
+use super::*;
+use crate::{Address, HashType, Scheme};
+
+/// BIP44 chain of an account.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Hash)]
+pub enum Chain {
+    /// External chain, used for receiving payments.
+    Receive,
+    /// Internal chain, used for change.
+    Change,
+}
+
+impl Chain {
+    /// The child number of the chain within an account.
+    pub fn index(self) -> u32 {
+        match self {
+            Chain::Receive => 0,
+            Chain::Change => 1,
+        }
+    }
+}
+
+impl ExtendedKey {
+    /// Convert to the extended public key, computing the public key of a private key.
+    pub fn to_public(&self) -> Result<ExtendedKey, DerivationError> {
+        Ok(ExtendedKey {
+            key: KeyData::Public(self.public_key()?.serialize()),
+            ..self.clone()
+        })
+    }
+
+    /// Derive the non-hardened child public key at `index`.
+    pub fn derive_public(&self, index: u32) -> Result<ExtendedKey, DerivationError> {
+        self.derive_public_with(&Secp256k1::verification_only(), index)
+    }
+
+    /// Derive the public key at a path of non-hardened child numbers relative to this key.
+    pub fn derive_public_path(&self, path: &[u32]) -> Result<ExtendedKey, DerivationError> {
+        let secp = Secp256k1::verification_only();
+        let mut key = self.to_public()?;
+        for index in path {
+            key = key.derive_public_with(&secp, *index)?;
+        }
+        Ok(key)
+    }
+
+    /// The P2PKH address of the public key.
+    pub fn to_address(&self) -> Result<Address, DerivationError> {
+        Ok(pubkey_address(
+            &self.public_key()?.serialize(),
+            self.network.clone(),
+        ))
+    }
+
+    /// Iterate over the addresses of a chain, treating this key as a BIP44 account key
+    /// such as `m/44'/145'/0'`.
+    pub fn addresses(&self, chain: Chain) -> Result<Addresses, DerivationError> {
+        let secp = Secp256k1::verification_only();
+        let chain_key = self.to_public()?.derive_public_with(&secp, chain.index())?;
+        Ok(Addresses {
+            secp,
+            chain_key,
+            next: 0,
+        })
+    }
+
+    /// Search the first `bound` indexes of a chain for the key paying to `address`, treating
+    /// this key as a BIP44 account key. Returns the path relative to this key.
+    ///
+    /// Addresses are compared on their Hash160, so may be given in either scheme and in
+    /// token-aware form.
+    pub fn find_address(
+        &self,
+        chain: Chain,
+        address: &Address,
+        bound: u32,
+    ) -> Result<Option<[u32; 2]>, DerivationError> {
+        match address.hash_type {
+            HashType::Key | HashType::TokenKey if address.body.len() == 20 => {}
+            _ => return Ok(None),
+        }
+        Ok(self
+            .addresses(chain)?
+            .take_while(|(index, _)| *index < bound)
+            .find(|(_, candidate)| candidate.body == address.body)
+            .map(|(index, _)| [chain.index(), index]))
+    }
+
+    // Parse the public key, computing it from the private key if necessary.
+    fn public_key(&self) -> Result<PublicKey, DerivationError> {
+        match &self.key {
+            KeyData::Public(key) => {
+                PublicKey::from_slice(key).map_err(|_| DerivationError::InvalidPublicKey)
+            }
+            KeyData::Private(secret) => {
+                let secret = SecretKey::from_slice(secret)
+                    .map_err(|_| DerivationError::InvalidPrivateKey)?;
+                Ok(PublicKey::from_secret_key(
+                    &Secp256k1::signing_only(),
+                    &secret,
+                ))
+            }
+        }
+    }
+
+    // Derive the child public key, reusing the verification context.
+    fn derive_public_with<C: Verification>(
+        &self,
+        secp: &Secp256k1<C>,
+        index: u32,
+    ) -> Result<ExtendedKey, DerivationError> {
+        if index >= HARDENED {
+            return Err(DerivationError::HardenedIndex(index));
+        }
+        let depth = self.depth.checked_add(1).ok_or(DerivationError::MaxDepth)?;
+        let parent = self.public_key()?;
+        let parent_bytes = parent.serialize();
+
+        // I = HMAC-SHA512(chain code, parent public key || index)
+        let mut engine = HmacEngine::<Sha512>::new(&self.chain_code);
+        engine.input(&parent_bytes);
+        engine.input(&index.to_be_bytes());
+        let hmac = Hmac::from_engine(engine);
+        let (tweak, chain_code) = hmac[..].split_at(32);
+
+        // The child is the parent plus the tweak point, which must be valid
+        let mut tweak_bytes = [0u8; 32];
+        tweak_bytes.copy_from_slice(tweak);
+        let child = Scalar::from_be_bytes(tweak_bytes)
+            .ok()
+            .and_then(|tweak| parent.add_exp_tweak(secp, &tweak).ok())
+            .ok_or(DerivationError::InvalidChild(index))?;
+
+        let mut parent_fingerprint = [0u8; 4];
+        parent_fingerprint.copy_from_slice(&Hash160::hash(&parent_bytes)[..4]);
+        let mut child_chain_code = [0u8; 32];
+        child_chain_code.copy_from_slice(chain_code);
+        Ok(ExtendedKey {
+            network: self.network.clone(),
+            depth,
+            parent_fingerprint,
+            child_number: index,
+            chain_code: child_chain_code,
+            key: KeyData::Public(child.serialize()),
+        })
+    }
+}
+
+// Build the P2PKH address of a SEC encoded public key.
+pub(crate) fn pubkey_address(key: &[u8], network: Network) -> Address {
+    Address {
+        body: Hash160::hash(key).to_vec(),
+        scheme: Scheme::CashAddr,
+        hash_type: HashType::Key,
+        network,
+    }
+}
+
+/// Iterator over the indexes and addresses of a chain, skipping indexes whose child key is
+/// invalid.
+pub struct Addresses {
+    secp: Secp256k1<secp256k1::VerifyOnly>,
+    chain_key: ExtendedKey,
+    next: u32,
+}
+
+impl Iterator for Addresses {
+    type Item = (u32, Address);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.next < HARDENED {
+            let index = self.next;
+            self.next += 1;
+            if let Ok(child) = self.chain_key.derive_public_with(&self.secp, index) {
+                if let Ok(key) = child.public_key() {
+                    return Some((index, pubkey_address(&key.serialize(), child.network)));
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AddressCodec, Base58Codec};
+
+    // BIP32 test vector 1, chains m/0', m/0'/1/2' and m/0'/1/2'/2
+    const XPUB_0H: &str = "xpub68Gmy5EdvgibQVfPdqkBBCHxA5htiqg55crXYuXoQRKfDBFA1WEjWgP6LHhwBZeNK1VTsfTFUHCdrfp1bgwQ9xv5ski8PX9rL2dZXvgGDnw";
+    const XPRV_0H: &str = "xprv9uHRZZhk6KAJC1avXpDAp4MDc3sQKNxDiPvvkX8Br5ngLNv1TxvUxt4cV1rGL5hj6KCesnDYUhd7oWgT11eZG7XnxHrnYeSvkzY7d2bhkJ7";
+    const XPUB_0H_1: &str = "xpub6ASuArnXKPbfEwhqN6e3mwBcDTgzisQN1wXN9BJcM47sSikHjJf3UFHKkNAWbWMiGj7Wf5uMash7SyYq527Hqck2AxYysAA7xmALppuCkwQ";
+    const XPUB_0H_1_2H: &str = "xpub6D4BDPcP2GT577Vvch3R8wDkScZWzQzMMUm3PWbmWvVJrZwQY4VUNgqFJPMM3No2dFDFGTsxxpG5uJh7n7epu4trkrX7x7DogT5Uv6fcLW5";
+    const XPUB_0H_1_2H_2: &str = "xpub6FHa3pjLCk84BayeJxFW2SP4XRrFd1JYnxeLeU8EqN3vDfZmbqBqaGJAyiLjTAwm6ZLRQUMv1ZACTj37sR62cfN7fe5JnJ7dh8zL4fiyLHV";
+
+    #[test]
+    fn derive_public() {
+        let xpub = ExtendedKey::decode(XPUB_0H).unwrap();
+        let child = xpub.derive_public(1).unwrap();
+        assert_eq!(child.encode().unwrap(), XPUB_0H_1);
+
+        let xpub = ExtendedKey::decode(XPUB_0H_1_2H).unwrap();
+        assert_eq!(
+            xpub.derive_public_path(&[2]).unwrap().encode().unwrap(),
+            XPUB_0H_1_2H_2
+        );
+        assert_eq!(
+            xpub.derive_public(HARDENED),
+            Err(DerivationError::HardenedIndex(HARDENED))
+        );
+    }
+
+    #[test]
+    fn private_keys() {
+        let xprv = ExtendedKey::decode(XPRV_0H).unwrap();
+        assert_eq!(xprv.to_public().unwrap().encode().unwrap(), XPUB_0H);
+        assert_eq!(xprv.derive_public(1).unwrap().encode().unwrap(), XPUB_0H_1);
+    }
+
+    #[test]
+    fn addresses() {
+        let xpub = ExtendedKey::decode(XPUB_0H).unwrap();
+        for chain in &[Chain::Receive, Chain::Change] {
+            let addresses: Vec<_> = xpub.addresses(*chain).unwrap().take(3).collect();
+            for (i, (index, address)) in addresses.iter().enumerate() {
+                let expected = xpub
+                    .derive_public_path(&[chain.index(), i as u32])
+                    .unwrap()
+                    .to_address()
+                    .unwrap();
+                assert_eq!(*index, i as u32);
+                assert_eq!(*address, expected);
+                assert_eq!(address.network, Network::Main);
+                assert!(address.is_standard());
+            }
+        }
+
+        // The chain m/0'/1 has the first address of m/0'
+        let (_, address) = xpub.addresses(Chain::Change).unwrap().next().unwrap();
+        let key = ExtendedKey::decode(XPUB_0H_1).unwrap();
+        assert_eq!(address, key.derive_public(0).unwrap().to_address().unwrap());
+    }
+
+    #[test]
+    fn find_address() {
+        let xpub = ExtendedKey::decode(XPUB_0H).unwrap();
+        let (_, address) = xpub.addresses(Chain::Change).unwrap().nth(5).unwrap();
+
+        // Either scheme and token-aware forms match
+        let legacy = Address {
+            scheme: Scheme::Base58,
+            ..address.clone()
+        };
+        let legacy = Base58Codec::decode(&legacy.encode().unwrap()).unwrap();
+        for candidate in &[address.clone(), legacy, address.clone().into_token_aware()] {
+            assert_eq!(
+                xpub.find_address(Chain::Change, candidate, 10).unwrap(),
+                Some([1, 5])
+            );
+        }
+
+        assert_eq!(xpub.find_address(Chain::Change, &address, 5).unwrap(), None);
+        assert_eq!(
+            xpub.find_address(Chain::Receive, &address, 10).unwrap(),
+            None
+        );
+        let script = Address {
+            hash_type: HashType::Script,
+            ..address
+        };
+        assert_eq!(xpub.find_address(Chain::Change, &script, 10).unwrap(), None);
+    }
+}
@@ -0,0 +1,138 @@
+use core::fmt;
+#[cfg(feature = "std")]
+use std::error::Error;
+
+use crate::{base58::Base58Error, Network, PublicKeyError};
+
+/// Error concerning encoding of extended keys.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum EncodingError {
+    /// Network has no extended key version bytes (network).
+    UnsupportedNetwork(Network),
+}
+
+impl fmt::Display for EncodingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EncodingError::UnsupportedNetwork(network) => {
+                write!(f, "unsupported network ({:?})", network)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Error for EncodingError {
+    fn cause(&self) -> Option<&dyn Error> {
+        None
+    }
+    fn description(&self) -> &str {
+        match *self {
+            EncodingError::UnsupportedNetwork(_) => "unsupported network",
+        }
+    }
+}
+
+/// Error concerning decoding of extended keys.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum DecodingError {
+    /// Base58Check decoding failed.
+    Base58(Base58Error),
+    /// Invalid length (length).
+    InvalidLength(usize),
+    /// Version bytes were not recognized.
+    InvalidVersion(u32),
+    /// Key data starts with a byte invalid for the key type (byte).
+    InvalidKeyPrefix(u8),
+    /// Depth zero key has a non-zero parent fingerprint or child number.
+    InvalidParent,
+    /// Public key is malformed.
+    InvalidPublicKey(PublicKeyError),
+    /// Private key is zero or not below the curve order.
+    InvalidPrivateKey,
+}
+
+impl From<Base58Error> for DecodingError {
+    fn from(err: Base58Error) -> Self {
+        DecodingError::Base58(err)
+    }
+}
+
+impl fmt::Display for DecodingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DecodingError::Base58(err) => write!(f, "base58 decoding failed: {}", err),
+            DecodingError::InvalidLength(length) => write!(f, "invalid length ({})", length),
+            DecodingError::InvalidVersion(v) => write!(f, "invalid version bytes ({:#010x})", v),
+            DecodingError::InvalidKeyPrefix(b) => write!(f, "invalid key prefix ({})", b),
+            DecodingError::InvalidParent => write!(f, "master key has a parent"),
+            DecodingError::InvalidPublicKey(err) => write!(f, "invalid public key: {}", err),
+            DecodingError::InvalidPrivateKey => write!(f, "invalid private key"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Error for DecodingError {
+    fn cause(&self) -> Option<&dyn Error> {
+        match self {
+            DecodingError::Base58(err) => Some(err),
+            DecodingError::InvalidPublicKey(err) => Some(err),
+            _ => None,
+        }
+    }
+    fn description(&self) -> &str {
+        match *self {
+            DecodingError::Base58(_) => "base58 decoding failed",
+            DecodingError::InvalidLength(_) => "invalid length",
+            DecodingError::InvalidVersion(_) => "invalid version",
+            DecodingError::InvalidKeyPrefix(_) => "invalid key prefix",
+            DecodingError::InvalidParent => "master key has a parent",
+            DecodingError::InvalidPublicKey(_) => "invalid public key",
+            DecodingError::InvalidPrivateKey => "invalid private key",
+        }
+    }
+}
+
+/// Error concerning derivation of child keys.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum DerivationError {
+    /// Hardened children cannot be derived from a public key (child number).
+    HardenedIndex(u32),
+    /// Public key is not a valid curve point.
+    InvalidPublicKey,
+    /// Private key is zero or not less than the curve order.
+    InvalidPrivateKey,
+    /// Child key is invalid, the next index should be used instead (child number).
+    InvalidChild(u32),
+    /// Key is already at the maximum depth.
+    MaxDepth,
+}
+
+impl fmt::Display for DerivationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DerivationError::HardenedIndex(index) => write!(f, "hardened index ({})", index),
+            DerivationError::InvalidPublicKey => write!(f, "invalid public key"),
+            DerivationError::InvalidPrivateKey => write!(f, "invalid private key"),
+            DerivationError::InvalidChild(index) => write!(f, "invalid child ({})", index),
+            DerivationError::MaxDepth => write!(f, "maximum depth reached"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Error for DerivationError {
+    fn cause(&self) -> Option<&dyn Error> {
+        None
+    }
+    fn description(&self) -> &str {
+        match *self {
+            DerivationError::HardenedIndex(_) => "hardened index",
+            DerivationError::InvalidPublicKey => "invalid public key",
+            DerivationError::InvalidPrivateKey => "invalid private key",
+            DerivationError::InvalidChild(_) => "invalid child",
+            DerivationError::MaxDepth => "maximum depth reached",
+        }
+    }
+}
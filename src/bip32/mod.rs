@@ -0,0 +1,333 @@
+#[cfg(feature = "secp256k1")]
+mod derivation;
+pub mod errors;
+
+use alloc::{string::String, vec::Vec};
+use core::fmt;
+
+use zeroize::{Zeroize, Zeroizing};
+
+use crate::{base58, check_pubkey, constant_time_eq, is_valid_secret, Network};
+#[cfg(feature = "secp256k1")]
+pub(crate) use derivation::pubkey_address;
+#[cfg(feature = "secp256k1")]
+pub use derivation::{Addresses, Chain};
+pub use errors::{DecodingError, DerivationError, EncodingError};
+
+/// Offset of hardened child numbers.
+pub const HARDENED: u32 = 0x8000_0000;
+
+// Version bytes
+const MAINNET_PUBLIC: u32 = 0x0488_b21e;
+const MAINNET_PRIVATE: u32 = 0x0488_ade4;
+const TESTNET_PUBLIC: u32 = 0x0435_87cf;
+const TESTNET_PRIVATE: u32 = 0x0435_8394;
+
+// Length of a serialized extended key
+const EXTENDED_KEY_LEN: usize = 78;
+
+// Maximum length of an encoded extended key, just over 82 * log_58(256)
+const MAX_ENCODED_LEN: usize = 112;
+
+/// Key material of an extended key.
+///
+/// Private keys are zeroized when dropped, and compared in constant time.
+#[derive(Clone)]
+pub enum KeyData {
+    /// Compressed SEC public key (key).
+    Public([u8; 33]),
+    /// Private key (secret).
+    Private([u8; 32]),
+}
+
+impl PartialEq for KeyData {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (KeyData::Public(key), KeyData::Public(other)) => key == other,
+            (KeyData::Private(secret), KeyData::Private(other)) => constant_time_eq(secret, other),
+            _ => false,
+        }
+    }
+}
+
+impl Eq for KeyData {}
+
+/// Omits the private key bytes.
+impl fmt::Debug for KeyData {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            KeyData::Public(key) => f.debug_tuple("Public").field(key).finish(),
+            KeyData::Private(_) => f.write_str("Private(..)"),
+        }
+    }
+}
+
+impl Drop for KeyData {
+    fn drop(&mut self) {
+        if let KeyData::Private(secret) = self {
+            secret.zeroize();
+        }
+    }
+}
+
+/// BIP32 extended public or private key.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct ExtendedKey {
+    /// Network, regtest keys share the testnet version bytes
+    pub network: Network,
+    /// Number of derivations from the master key
+    pub depth: u8,
+    /// First four bytes of the Hash160 of the parent public key
+    pub parent_fingerprint: [u8; 4],
+    /// Index of this key within its parent, hardened from 2^31
+    pub child_number: u32,
+    /// Chain code
+    pub chain_code: [u8; 32],
+    /// Public or private key
+    pub key: KeyData,
+}
+
+impl ExtendedKey {
+    /// Whether the extended key holds a private key.
+    pub fn is_private(&self) -> bool {
+        match self.key {
+            KeyData::Public(_) => false,
+            KeyData::Private(_) => true,
+        }
+    }
+
+    /// Attempt to convert the extended key to a Base58Check string.
+    pub fn encode(&self) -> Result<String, EncodingError> {
+        let version = match (&self.network, &self.key) {
+            (Network::Main, KeyData::Public(_)) => MAINNET_PUBLIC,
+            (Network::Main, KeyData::Private(_)) => MAINNET_PRIVATE,
+            (Network::Test, KeyData::Public(_)) | (Network::Regtest, KeyData::Public(_)) => {
+                TESTNET_PUBLIC
+            }
+            (Network::Test, KeyData::Private(_)) | (Network::Regtest, KeyData::Private(_)) => {
+                TESTNET_PRIVATE
+            }
+            (Network::Other(_), _) => {
+                return Err(EncodingError::UnsupportedNetwork(self.network.clone()))
+            }
+        };
+
+        let mut payload = Zeroizing::new(Vec::with_capacity(EXTENDED_KEY_LEN));
+        payload.extend_from_slice(&version.to_be_bytes());
+        payload.push(self.depth);
+        payload.extend_from_slice(&self.parent_fingerprint);
+        payload.extend_from_slice(&self.child_number.to_be_bytes());
+        payload.extend_from_slice(&self.chain_code);
+        match &self.key {
+            KeyData::Public(key) => payload.extend_from_slice(key),
+            KeyData::Private(secret) => {
+                payload.push(0);
+                payload.extend_from_slice(secret);
+            }
+        }
+        Ok(base58::encode_check(&payload))
+    }
+
+    /// Attempt to convert a Base58Check string to an extended key.
+    pub fn decode(key_str: &str) -> Result<Self, DecodingError> {
+        // Reject overlong strings before the quadratic decoding
+        if key_str.len() > MAX_ENCODED_LEN {
+            return Err(DecodingError::InvalidLength(key_str.len()));
+        }
+        let payload = base58::decode_check_zeroizing(key_str)?;
+        let length = payload.len();
+        if length != EXTENDED_KEY_LEN {
+            return Err(DecodingError::InvalidLength(length));
+        }
+
+        // Parse network and key type
+        let version = u32::from_be_bytes([payload[0], payload[1], payload[2], payload[3]]);
+        let (network, private) = match version {
+            MAINNET_PUBLIC => (Network::Main, false),
+            MAINNET_PRIVATE => (Network::Main, true),
+            TESTNET_PUBLIC => (Network::Test, false),
+            TESTNET_PRIVATE => (Network::Test, true),
+            _ => return Err(DecodingError::InvalidVersion(version)),
+        };
+
+        // Check the master key has no parent
+        let depth = payload[4];
+        let mut parent_fingerprint = [0u8; 4];
+        parent_fingerprint.copy_from_slice(&payload[5..9]);
+        let child_number = u32::from_be_bytes([payload[9], payload[10], payload[11], payload[12]]);
+        if depth == 0 && (parent_fingerprint != [0; 4] || child_number != 0) {
+            return Err(DecodingError::InvalidParent);
+        }
+
+        let mut chain_code = [0u8; 32];
+        chain_code.copy_from_slice(&payload[13..45]);
+
+        // Check the key prefix and that the key is valid
+        let key_bytes = &payload[45..];
+        let key = match (private, key_bytes[0]) {
+            (false, 0x02) | (false, 0x03) => {
+                check_pubkey(key_bytes).map_err(DecodingError::InvalidPublicKey)?;
+                let mut key = [0u8; 33];
+                key.copy_from_slice(key_bytes);
+                KeyData::Public(key)
+            }
+            (true, 0x00) => {
+                if !is_valid_secret(&key_bytes[1..]) {
+                    return Err(DecodingError::InvalidPrivateKey);
+                }
+                let mut secret = [0u8; 32];
+                secret.copy_from_slice(&key_bytes[1..]);
+                KeyData::Private(secret)
+            }
+            (_, prefix) => return Err(DecodingError::InvalidKeyPrefix(prefix)),
+        };
+
+        Ok(ExtendedKey {
+            network,
+            depth,
+            parent_fingerprint,
+            child_number,
+            chain_code,
+            key,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PublicKeyError;
+    use alloc::vec;
+
+    // BIP32 test vector 1, chain m/0'
+    const XPUB: &str = "xpub68Gmy5EdvgibQVfPdqkBBCHxA5htiqg55crXYuXoQRKfDBFA1WEjWgP6LHhwBZeNK1VTsfTFUHCdrfp1bgwQ9xv5ski8PX9rL2dZXvgGDnw";
+    const XPRV: &str = "xprv9uHRZZhk6KAJC1avXpDAp4MDc3sQKNxDiPvvkX8Br5ngLNv1TxvUxt4cV1rGL5hj6KCesnDYUhd7oWgT11eZG7XnxHrnYeSvkzY7d2bhkJ7";
+
+    #[test]
+    fn mainnet() {
+        let xpub = ExtendedKey::decode(XPUB).unwrap();
+        assert_eq!(xpub.network, Network::Main);
+        assert_eq!(xpub.depth, 1);
+        assert_eq!(xpub.parent_fingerprint, [0x34, 0x42, 0x19, 0x3e]);
+        assert_eq!(xpub.child_number, 0x8000_0000);
+        assert_eq!(
+            hex::encode(xpub.chain_code),
+            "47fdacbd0f1097043b78c63c20c34ef4ed9a111d980047ad16282c7ae6236141"
+        );
+        assert!(!xpub.is_private());
+        assert_eq!(xpub.encode().unwrap(), XPUB);
+
+        let xprv = ExtendedKey::decode(XPRV).unwrap();
+        assert!(xprv.is_private());
+        assert_eq!(xprv.chain_code, xpub.chain_code);
+        assert_eq!(
+            xprv.key,
+            KeyData::Private({
+                let mut secret = [0u8; 32];
+                secret.copy_from_slice(
+                    &hex::decode(
+                        "edb2e14f9ee77d26dd93b4ecede8d16ed408ce149b6cd80b0715a2d911a0afea",
+                    )
+                    .unwrap(),
+                );
+                secret
+            })
+        );
+        assert_eq!(xprv.encode().unwrap(), XPRV);
+    }
+
+    #[test]
+    fn testnet() {
+        let mut key = ExtendedKey::decode(XPUB).unwrap();
+        key.network = Network::Regtest;
+        let tpub = key.encode().unwrap();
+        assert!(tpub.starts_with("tpub"));
+        key.network = Network::Test;
+        assert_eq!(ExtendedKey::decode(&tpub).unwrap(), key);
+
+        let mut key = ExtendedKey::decode(XPRV).unwrap();
+        key.network = Network::Test;
+        let tprv = key.encode().unwrap();
+        assert!(tprv.starts_with("tprv"));
+        assert_eq!(ExtendedKey::decode(&tprv).unwrap(), key);
+
+        key.network = Network::Other("ecash".into());
+        assert_eq!(
+            key.encode(),
+            Err(EncodingError::UnsupportedNetwork(key.network.clone()))
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let payload = base58::decode_check(XPUB).unwrap();
+        let with_payload = |f: &dyn Fn(&mut Vec<u8>)| {
+            let mut payload = payload.clone();
+            f(&mut payload);
+            ExtendedKey::decode(&base58::encode_check(&payload))
+        };
+
+        assert_eq!(
+            with_payload(&|p| p.truncate(77)),
+            Err(DecodingError::InvalidLength(77))
+        );
+        assert_eq!(
+            with_payload(&|p| p[0] = 0),
+            Err(DecodingError::InvalidVersion(0x0088_b21e))
+        );
+        assert_eq!(
+            with_payload(&|p| p[45] = 0x04),
+            Err(DecodingError::InvalidKeyPrefix(0x04))
+        );
+        assert_eq!(
+            with_payload(&|p| p[4] = 0),
+            Err(DecodingError::InvalidParent)
+        );
+        assert_eq!(
+            with_payload(&|p| p[..4].copy_from_slice(&MAINNET_PRIVATE.to_be_bytes())),
+            Err(DecodingError::InvalidKeyPrefix(0x03))
+        );
+        assert!(matches!(
+            ExtendedKey::decode(&XPUB[1..]),
+            Err(DecodingError::Base58(_))
+        ));
+        assert_eq!(
+            with_payload(&|p| *p = vec![0; 78]),
+            Err(DecodingError::InvalidVersion(0))
+        );
+        assert_eq!(
+            ExtendedKey::decode(&"x".repeat(100_000)),
+            Err(DecodingError::InvalidLength(100_000))
+        );
+
+        // BIP32 test vector 5: public key not on the curve, private keys 0 and n
+        let off_curve = with_payload(&|p| {
+            p[45..].copy_from_slice(&[0; 33]);
+            p[45] = 0x02;
+            p[77] = 0x07;
+        });
+        if cfg!(feature = "secp256k1") {
+            assert_eq!(
+                off_curve,
+                Err(DecodingError::InvalidPublicKey(PublicKeyError::NotOnCurve))
+            );
+        } else {
+            assert!(off_curve.is_ok());
+        }
+        let private = base58::decode_check(XPRV).unwrap();
+        for secret in &[[0u8; 32], crate::CURVE_ORDER] {
+            let mut payload = private.clone();
+            payload[46..].copy_from_slice(secret);
+            assert_eq!(
+                ExtendedKey::decode(&base58::encode_check(&payload)),
+                Err(DecodingError::InvalidPrivateKey)
+            );
+        }
+    }
+
+    #[test]
+    fn debug_omits_secret() {
+        let xprv = ExtendedKey::decode(XPRV).unwrap();
+        assert!(alloc::format!("{:?}", xprv).contains("key: Private(..)"));
+    }
+}
@@ -0,0 +1,152 @@
+// Table-driven implementation of the cashaddr BCH checksum.
+// https://github.com/Bitcoin-ABC/bitcoin-abc/blob/2804a49bfc0764ba02ce2999809c52b3b9bb501e/src/cashaddr.cpp#L42
+
+use super::{MAINNET_PREFIX, REGNET_PREFIX, TESTNET_PREFIX};
+
+// Mask of the state bits which are not shifted out by a single step.
+const STEP_MASK: u64 = 0x0007_ffff_ffff;
+
+// Mask of the state bits which are not shifted out by two steps.
+const DOUBLE_STEP_MASK: u64 = 0x3fff_ffff;
+
+// Generator terms, XORed into the state for each bit of the symbol shifted out.
+const GENERATORS: [u64; 5] = [
+    0x0098_f2bc_8e61,
+    0x0079_b76d_99e2,
+    0x00f3_3e5f_b3c4,
+    0x00ae_2eab_e2a8,
+    0x001e_4f43_e470,
+];
+
+// Combined generator terms for each 5 bit symbol shifted out by a single step.
+const STEP_TABLE: [u64; 32] = step_table();
+
+// Combined generator terms for each 10 bits shifted out by two steps.
+const DOUBLE_STEP_TABLE: [u64; 1024] = double_step_table();
+
+// Checksum states after the expanded known prefixes.
+const MAINNET_STATE: u64 = expanded_prefix_state(MAINNET_PREFIX);
+const TESTNET_STATE: u64 = expanded_prefix_state(TESTNET_PREFIX);
+const REGNET_STATE: u64 = expanded_prefix_state(REGNET_PREFIX);
+
+const fn step_table() -> [u64; 32] {
+    let mut table = [0; 32];
+    let mut i = 0;
+    while i < 32 {
+        let mut j = 0;
+        while j < 5 {
+            if (i >> j) & 1 != 0 {
+                table[i] ^= GENERATORS[j];
+            }
+            j += 1;
+        }
+        i += 1;
+    }
+    table
+}
+
+const fn double_step_table() -> [u64; 1024] {
+    let mut table = [0; 1024];
+    let mut i = 0;
+    while i < 1024 {
+        table[i] = polymod_step(polymod_step((i as u64) << 30, 0), 0);
+        i += 1;
+    }
+    table
+}
+
+// Feed a single 5 bit symbol into the checksum state.
+pub const fn polymod_step(c: u64, d: u8) -> u64 {
+    ((c & STEP_MASK) << 5) ^ (d as u64) ^ STEP_TABLE[(c >> 35) as usize]
+}
+
+// Feed two 5 bit symbols into the checksum state.
+const fn polymod_double_step(c: u64, d0: u8, d1: u8) -> u64 {
+    ((c & DOUBLE_STEP_MASK) << 10)
+        ^ ((d0 as u64) << 5)
+        ^ (d1 as u64)
+        ^ DOUBLE_STEP_TABLE[(c >> 30) as usize]
+}
+
+// Feed a 5 bit array into the checksum state.
+pub fn polymod_update(c: u64, data: &[u8]) -> u64 {
+    let mut pairs = data.chunks_exact(2);
+    let c = pairs
+        .by_ref()
+        .fold(c, |c, pair| polymod_double_step(c, pair[0], pair[1]));
+    pairs.remainder().iter().fold(c, |c, d| polymod_step(c, *d))
+}
+
+// Compute the checksum state after the expanded prefix.
+const fn expanded_prefix_state(prefix: &str) -> u64 {
+    let bytes = prefix.as_bytes();
+    let mut c = 1;
+    let mut i = 0;
+    while i < bytes.len() {
+        c = polymod_step(c, bytes[i] & 0x1f);
+        i += 1;
+    }
+    polymod_step(c, 0)
+}
+
+// Get the checksum state after the expanded prefix, precomputed for the known prefixes.
+pub fn prefix_state(prefix: &str) -> u64 {
+    match prefix {
+        MAINNET_PREFIX => MAINNET_STATE,
+        TESTNET_PREFIX => TESTNET_STATE,
+        REGNET_PREFIX => REGNET_STATE,
+        _ => expanded_prefix_state(prefix),
+    }
+}
+
+// Compute the residue of the prefix and 5 bit payload, which is zero for a valid checksum.
+pub fn polymod(prefix: &str, payload: &[u8]) -> u64 {
+    polymod_update(prefix_state(prefix), payload) ^ 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    // Bitwise reference implementation
+    fn reference_polymod(v: &[u8]) -> u64 {
+        let mut c: u64 = 1;
+        for d in v.iter() {
+            let c0: u8 = (c >> 35) as u8;
+            c = ((c & 0x0007_ffff_ffff) << 5) ^ u64::from(*d);
+            for (i, generator) in GENERATORS.iter().enumerate() {
+                if c0 & (1 << i) != 0 {
+                    c ^= generator;
+                }
+            }
+        }
+        c ^ 1
+    }
+
+    fn reference_expand_prefix(prefix: &str) -> Vec<u8> {
+        prefix.bytes().map(|b| b & 0x1f).chain(Some(0)).collect()
+    }
+
+    #[test]
+    fn matches_reference() {
+        // Deterministic pseudorandom 5 bit symbols
+        let mut x: u32 = 0x1234_5678;
+        let data: Vec<u8> = (0..113)
+            .map(|_| {
+                x ^= x << 13;
+                x ^= x >> 17;
+                x ^= x << 5;
+                (x & 31) as u8
+            })
+            .collect();
+
+        for prefix in &["bitcoincash", "bchtest", "bchreg", "ecash", "p"] {
+            for len in 0..data.len() {
+                let expected =
+                    reference_polymod(&[&reference_expand_prefix(prefix), &data[..len]].concat());
+                assert_eq!(polymod(prefix, &data[..len]), expected);
+            }
+        }
+    }
+}
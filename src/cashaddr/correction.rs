@@ -0,0 +1,228 @@
+use super::*;
+
+// Maximum number of substituted characters searched for
+const MAX_ERRORS: usize = 2;
+
+/// Likely substitution errors in a cashaddr whose checksum failed.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Correction {
+    /// Character positions within the address string which are likely wrong, in ascending order.
+    pub positions: Vec<usize>,
+    /// The corrected address string, if exactly one correction was found.
+    pub corrected: Option<String>,
+}
+
+// A single substitution: the payload position and the 5 bit value XORed into it.
+type Substitution = (usize, u8);
+
+impl CashAddrCodec {
+    /// Attempt to locate, and where unambiguous correct, substituted characters in a cashaddr.
+    ///
+    /// Corrections of up to two substituted payload characters are searched for, preferring
+    /// those with the fewest substitutions. A valid address yields no positions and is returned
+    /// unchanged. If no correction is found, the `DecodingError::ChecksumFailed` of the original
+    /// string is returned. Payloads longer than any valid cashaddr are rejected with
+    /// `DecodingError::InvalidLength` before searching.
+    pub fn locate_errors(addr_str: &str) -> Result<Correction, DecodingError> {
+        let (prefix, payload_str) = split_prefix(addr_str)?;
+        let prefix: &str = &prefix;
+        let original_prefix = &addr_str[..prefix.len()];
+        let network = prefix_network(prefix);
+        if payload_str.len() > MAX_PAYLOAD_LEN {
+            return Err(DecodingError::InvalidLength(payload_str.len()));
+        }
+
+        let mut payload_5_bits = Vec::new();
+        Self::payload_to_5_bits(payload_str, &mut payload_5_bits)?;
+        let residue = polymod(prefix, &payload_5_bits);
+        if residue == 0 {
+            return Ok(Correction {
+                positions: vec![],
+                corrected: Some(addr_str.to_string()),
+            });
+        }
+
+        // Find the substitutions which cancel the residue and leave a decodable payload
+        let uppercase = payload_str.chars().any(|c| c.is_uppercase());
+        let correct = |substitutions: &[Substitution]| -> Option<String> {
+            let mut corrected = payload_5_bits.clone();
+            for (position, error) in substitutions {
+                corrected[*position] ^= error;
+            }
+            let corrected_str: String = corrected
+                .iter()
+                .map(|d| {
+                    let c = CHARSET[*d as usize] as char;
+                    if uppercase {
+                        c.to_ascii_uppercase()
+                    } else {
+                        c
+                    }
+                })
+                .collect();
+            Self::decode_payload(&corrected_str, prefix, network.to_owned(), false)
+                .ok()
+                .map(|_| [original_prefix, ":", &corrected_str].concat())
+        };
+
+        let table = error_table(payload_5_bits.len());
+        let mut candidates = Vec::new();
+        for num_errors in 1..=MAX_ERRORS {
+            for substitutions in find_substitutions(&table, residue, num_errors) {
+                if let Some(corrected) = correct(&substitutions) {
+                    candidates.push((substitutions, corrected));
+                }
+            }
+            if !candidates.is_empty() {
+                break;
+            }
+        }
+        if candidates.is_empty() {
+            return Err(DecodingError::ChecksumFailed(residue));
+        }
+
+        // Report positions relative to the whole string
+        let offset = prefix.len() + 1;
+        let mut positions: Vec<usize> = candidates
+            .iter()
+            .flat_map(|(substitutions, _)| substitutions.iter().map(|(i, _)| offset + i))
+            .collect();
+        positions.sort_unstable();
+        positions.dedup();
+        let corrected = if candidates.len() == 1 {
+            candidates.pop().map(|(_, corrected)| corrected)
+        } else {
+            None
+        };
+        Ok(Correction {
+            positions,
+            corrected,
+        })
+    }
+}
+
+// The checksum is linear, so XORing `error` into the symbol at `position` changes the
+// residue by a fixed amount. Tabulate this change for every substitution, sorted by it.
+fn error_table(len: usize) -> Vec<(u64, Substitution)> {
+    let mut table = Vec::with_capacity(len * 31);
+    for error in 1..32u8 {
+        let mut c = u64::from(error);
+        for position in (0..len).rev() {
+            table.push((c, (position, error)));
+            c = polymod_step(c, 0);
+        }
+    }
+    table.sort_unstable();
+    table
+}
+
+// Find sets of substitutions at distinct positions whose residue changes sum to `residue`.
+fn find_substitutions(
+    table: &[(u64, Substitution)],
+    residue: u64,
+    num_errors: usize,
+) -> Vec<Vec<Substitution>> {
+    let lookup = |target: u64| {
+        let start = table.partition_point(|(c, _)| *c < target);
+        table[start..]
+            .iter()
+            .take_while(move |(c, _)| *c == target)
+            .map(|(_, substitution)| *substitution)
+    };
+    match num_errors {
+        1 => lookup(residue)
+            .map(|substitution| vec![substitution])
+            .collect(),
+        2 => table
+            .iter()
+            .flat_map(|(c, first)| {
+                lookup(residue ^ c)
+                    .filter(move |second| first.0 < second.0)
+                    .map(move |second| vec![*first, second])
+            })
+            .collect(),
+        _ => vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ADDR: &str = "bitcoincash:qr6m7j9njldwwzlg9v7v53unlr4jkmx6eylep8ekg2";
+
+    fn substitute(addr: &str, position: usize, c: char) -> String {
+        addr.chars()
+            .enumerate()
+            .map(|(i, d)| if i == position { c } else { d })
+            .collect()
+    }
+
+    #[test]
+    fn valid_address() {
+        let correction = CashAddrCodec::locate_errors(ADDR).unwrap();
+        assert!(correction.positions.is_empty());
+        assert_eq!(correction.corrected.as_deref(), Some(ADDR));
+    }
+
+    #[test]
+    fn single_error() {
+        for position in &[12, 20, 40, ADDR.len() - 1] {
+            let typo = substitute(ADDR, *position, 'x');
+            let correction = CashAddrCodec::locate_errors(&typo).unwrap();
+            assert_eq!(correction.positions, vec![*position]);
+            assert_eq!(correction.corrected.as_deref(), Some(ADDR));
+        }
+    }
+
+    #[test]
+    fn double_error() {
+        let typo = substitute(&substitute(ADDR, 15, 'q'), 33, '8');
+        let correction = CashAddrCodec::locate_errors(&typo).unwrap();
+        assert_eq!(correction.positions, vec![15, 33]);
+        assert_eq!(correction.corrected.as_deref(), Some(ADDR));
+    }
+
+    #[test]
+    fn uppercase_error() {
+        let upper = ADDR.to_ascii_uppercase();
+        let typo = substitute(&upper, 30, 'X');
+        let correction = CashAddrCodec::locate_errors(&typo).unwrap();
+        assert_eq!(correction.positions, vec![30]);
+        assert_eq!(correction.corrected, Some(upper));
+    }
+
+    #[test]
+    fn uncorrectable() {
+        let typo = substitute(&substitute(&substitute(ADDR, 15, 'q'), 33, '8'), 45, 'z');
+        assert!(matches!(
+            CashAddrCodec::locate_errors(&typo),
+            Err(DecodingError::ChecksumFailed(_))
+        ));
+    }
+
+    #[test]
+    fn overlong_payload() {
+        let long = ["bitcoincash:", &"q".repeat(MAX_PAYLOAD_LEN + 1)].concat();
+        assert_eq!(
+            CashAddrCodec::locate_errors(&long),
+            Err(DecodingError::InvalidLength(MAX_PAYLOAD_LEN + 1))
+        );
+
+        // The longest valid payload is still searched
+        let data = [0x42; 64];
+        let addr = CashAddrCodec::encode(&data, HashType::Key, Network::Main).unwrap();
+        assert_eq!(addr.len(), "bitcoincash:".len() + MAX_PAYLOAD_LEN);
+        let typo = substitute(
+            &addr,
+            20,
+            if addr.as_bytes()[20] == b'q' {
+                'p'
+            } else {
+                'q'
+            },
+        );
+        let correction = CashAddrCodec::locate_errors(&typo).unwrap();
+        assert_eq!(correction.corrected, Some(addr));
+    }
+}
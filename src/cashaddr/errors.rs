@@ -1,21 +1,61 @@
-use std::{error::Error, fmt};
+use alloc::{string::String, vec::Vec};
+use core::fmt;
+#[cfg(feature = "std")]
+use std::error::Error;
+
+use crate::{HashType, Network};
 
 /// Error concerning encoding of cashaddrs.
 #[derive(Debug, PartialEq, Eq, Clone)]
-pub struct EncodingError(pub usize);
+pub enum EncodingError {
+    /// Invalid length (length).
+    InvalidLength(usize),
+    /// Prefix is empty or contains characters other than lowercase ASCII letters and digits (prefix).
+    InvalidPrefix(String),
+    /// Output buffer is too small.
+    BufferTooSmall,
+    /// Unknown address type is out of range or collides with a known type (type).
+    InvalidType(u8),
+    /// Writing to the formatter failed.
+    Fmt(fmt::Error),
+}
+
+impl From<fmt::Error> for EncodingError {
+    fn from(err: fmt::Error) -> Self {
+        EncodingError::Fmt(err)
+    }
+}
 
 impl fmt::Display for EncodingError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "invalid length ({})", self.0)
+        match self {
+            EncodingError::InvalidLength(length) => write!(f, "invalid length ({})", length),
+            EncodingError::InvalidPrefix(prefix) => write!(f, "invalid prefix ({})", prefix),
+            EncodingError::BufferTooSmall => write!(f, "buffer too small"),
+            EncodingError::InvalidType(address_type) => {
+                write!(f, "invalid address type ({})", address_type)
+            }
+            EncodingError::Fmt(err) => write!(f, "formatting failed ({})", err),
+        }
     }
 }
 
+#[cfg(feature = "std")]
 impl Error for EncodingError {
     fn cause(&self) -> Option<&dyn Error> {
-        None
+        match self {
+            EncodingError::Fmt(err) => Some(err),
+            _ => None,
+        }
     }
     fn description(&self) -> &str {
-        "invalid length"
+        match *self {
+            EncodingError::InvalidLength(_) => "invalid length",
+            EncodingError::InvalidPrefix(_) => "invalid prefix",
+            EncodingError::BufferTooSmall => "buffer too small",
+            EncodingError::InvalidType(_) => "invalid address type",
+            EncodingError::Fmt(_) => "formatting failed",
+        }
     }
 }
 
@@ -26,7 +66,7 @@ pub enum DecodingError {
     InvalidLength(usize),
     /// Zero or multiple prefixes.
     NoPrefix,
-    /// Failed to match known prefixes (prefix).
+    /// Prefix is empty or contains characters other than lowercase ASCII letters and digits (prefix).
     InvalidPrefix(String),
     /// Checksum failed (checksum).
     ChecksumFailed(u64),
@@ -36,6 +76,16 @@ pub enum DecodingError {
     InvalidVersion(u8),
     /// Upper and lowercase address string.
     MixedCase,
+    /// Checksum of prefix-less address failed against every candidate network.
+    NoMatchingPrefix,
+    /// Checksum of prefix-less address validated against multiple networks (networks).
+    AmbiguousPrefix(Vec<Network>),
+    /// Padding bits are non-zero or span a whole 5 bit group.
+    InvalidPadding,
+    /// Payload too short to contain a version byte and checksum (length).
+    TooShort(usize),
+    /// Hash type and length do not correspond to a standard locking script.
+    NonStandard { hash_type: HashType, length: usize },
 }
 
 impl fmt::Display for DecodingError {
@@ -50,10 +100,22 @@ impl fmt::Display for DecodingError {
             DecodingError::InvalidVersion(c) => write!(f, "invalid version byte ({})", c),
             DecodingError::InvalidPrefix(prefix) => write!(f, "invalid prefix ({})", prefix),
             DecodingError::InvalidLength(length) => write!(f, "invalid length ({})", length),
+            DecodingError::NoMatchingPrefix => write!(f, "no matching prefix"),
+            DecodingError::AmbiguousPrefix(networks) => {
+                write!(f, "ambiguous prefix ({:?})", networks)
+            }
+            DecodingError::InvalidPadding => write!(f, "invalid padding"),
+            DecodingError::TooShort(length) => write!(f, "payload too short ({})", length),
+            DecodingError::NonStandard { hash_type, length } => write!(
+                f,
+                "non-standard address ({:?} with length {})",
+                hash_type, length
+            ),
         }
     }
 }
 
+#[cfg(feature = "std")]
 impl Error for DecodingError {
     fn cause(&self) -> Option<&dyn Error> {
         None
@@ -67,6 +129,11 @@ impl Error for DecodingError {
             DecodingError::InvalidVersion(_) => "invalid version byte",
             DecodingError::InvalidPrefix(_) => "invalid prefix",
             DecodingError::InvalidLength(_) => "invalid length",
+            DecodingError::NoMatchingPrefix => "no matching prefix",
+            DecodingError::AmbiguousPrefix(_) => "ambiguous prefix",
+            DecodingError::InvalidPadding => "invalid padding",
+            DecodingError::TooShort(_) => "payload too short",
+            DecodingError::NonStandard { .. } => "non-standard address",
         }
     }
 }
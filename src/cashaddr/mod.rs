@@ -1,7 +1,20 @@
+mod checksum;
+mod correction;
 pub mod errors;
+mod version;
+
+use alloc::{
+    borrow::{Cow, ToOwned},
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{fmt, iter};
 
 use super::*;
+use checksum::{polymod, polymod_step, polymod_update, prefix_state};
+pub use correction::Correction;
 pub use errors::{DecodingError, EncodingError};
+pub use version::VersionByte;
 
 // Prefixes
 const MAINNET_PREFIX: &str = "bitcoincash";
@@ -11,6 +24,9 @@ const REGNET_PREFIX: &str = "bchreg";
 // The cashaddr character set for encoding
 const CHARSET: &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
 
+// The uppercase cashaddr character set for encoding
+const CHARSET_UPPER: &[u8; 32] = b"QPZRY9X8GF2TVDW0S3JN54KHCE6MUA7L";
+
 // The cashaddr character set for decoding
 #[rustfmt::skip]
 const CHARSET_REV: [Option<u8>; 128] = [
@@ -32,60 +48,91 @@ const CHARSET_REV: [Option<u8>; 128] = [
     Some(6),  Some(4),  Some(2),  None,     None,     None,     None,     None,
 ];
 
-// Version byte flags
-#[allow(dead_code)]
-mod version_byte_flags {
-    pub const TYPE_MASK: u8 = 0x78;
-    pub const TYPE_P2PKH: u8 = 0x00;
-    pub const TYPE_P2SH: u8 = 0x08;
-
-    pub const SIZE_MASK: u8 = 0x07;
-    pub const SIZE_160: u8 = 0x00;
-    pub const SIZE_192: u8 = 0x01;
-    pub const SIZE_224: u8 = 0x02;
-    pub const SIZE_256: u8 = 0x03;
-    pub const SIZE_320: u8 = 0x04;
-    pub const SIZE_384: u8 = 0x05;
-    pub const SIZE_448: u8 = 0x06;
-    pub const SIZE_512: u8 = 0x07;
+// Minimum number of 5 bit symbols in a payload: a version byte and the checksum
+const MIN_PAYLOAD_LEN: usize = 2 + 8;
+
+// Maximum number of 5 bit symbols in a payload: a version byte, a 64-byte hash and the
+// checksum
+const MAX_PAYLOAD_LEN: usize = 104 + 8;
+
+// Check the prefix is non-empty and consists of lowercase ASCII letters and digits.
+fn is_valid_prefix(prefix: &str) -> bool {
+    !prefix.is_empty()
+        && prefix
+            .bytes()
+            .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit())
 }
 
-// https://github.com/Bitcoin-ABC/bitcoin-abc/blob/2804a49bfc0764ba02ce2999809c52b3b9bb501e/src/cashaddr.cpp#L42
-fn polymod(v: &[u8]) -> u64 {
-    let mut c: u64 = 1;
-    for d in v.iter() {
-        let c0: u8 = (c >> 35) as u8;
-        c = ((c & 0x0007_ffff_ffff) << 5) ^ u64::from(*d);
-        if c0 & 0x01 != 0 {
-            c ^= 0x0098_f2bc_8e61;
-        }
-        if c0 & 0x02 != 0 {
-            c ^= 0x0079_b76d_99e2;
-        }
-        if c0 & 0x04 != 0 {
-            c ^= 0x00f3_3e5f_b3c4;
-        }
-        if c0 & 0x08 != 0 {
-            c ^= 0x00ae_2eab_e2a8;
-        }
-        if c0 & 0x10 != 0 {
-            c ^= 0x001e_4f43_e470;
-        }
+// Check whether the string contains both upper and lowercase characters.
+fn is_mixed_case(s: &str) -> bool {
+    s.chars().any(|c| c.is_lowercase()) && s.chars().any(|c| c.is_uppercase())
+}
+
+// Split the address into its lowercased prefix and the payload.
+// The whole string must be either upper or lowercase.
+fn split_prefix(addr_str: &str) -> Result<(Cow<'_, str>, &str), DecodingError> {
+    // Delimit and extract prefix
+    let mut parts = addr_str.split(':');
+    let (prefix, payload_str) = match (parts.next(), parts.next(), parts.next()) {
+        (Some(prefix), Some(payload_str), None) => (prefix, payload_str),
+        _ => return Err(DecodingError::NoPrefix),
+    };
+    if is_mixed_case(addr_str) {
+        return Err(DecodingError::MixedCase);
+    }
+    let lowercase_prefix = if prefix.bytes().any(|b| b.is_ascii_uppercase()) {
+        Cow::Owned(prefix.to_ascii_lowercase())
+    } else {
+        Cow::Borrowed(prefix)
+    };
+    if !is_valid_prefix(&lowercase_prefix) {
+        return Err(DecodingError::InvalidPrefix(prefix.to_string()));
     }
-    c ^ 1
+    Ok((lowercase_prefix, payload_str))
 }
 
-// Expand the address prefix for the checksum operation.
-fn expand_prefix(prefix: &str) -> Vec<u8> {
-    let mut ret: Vec<u8> = prefix.chars().map(|c| (c as u8) & 0x1f).collect();
-    ret.push(0);
-    ret
+// Get the prefix used by the network. Other networks may not reuse a built-in prefix, as
+// their addresses would decode as the built-in network.
+fn network_prefix(network: &Network) -> Result<&str, String> {
+    match network {
+        Network::Main => Ok(MAINNET_PREFIX),
+        Network::Test => Ok(TESTNET_PREFIX),
+        Network::Regtest => Ok(REGNET_PREFIX),
+        Network::Other(prefix) => match prefix.as_str() {
+            MAINNET_PREFIX | TESTNET_PREFIX | REGNET_PREFIX => Err(prefix.clone()),
+            _ => Ok(prefix),
+        },
+    }
+}
+
+// Get the network identified by the prefix.
+fn prefix_network(prefix: &str) -> Network {
+    match prefix {
+        MAINNET_PREFIX => Network::Main,
+        TESTNET_PREFIX => Network::Test,
+        REGNET_PREFIX => Network::Regtest,
+        _ => Network::Other(prefix.to_string()),
+    }
 }
 
-fn convert_bits(data: &[u8], inbits: u8, outbits: u8, pad: bool) -> Vec<u8> {
+// Length of the encoded payload and checksum for a body of the given length.
+fn encoded_len(body_len: usize) -> usize {
+    ((1 + body_len) * 8).div_ceil(5) + 8
+}
+
+// Regroup `data` from `inbits` to `outbits` bit groups. Without `pad`, the leftover bits
+// must be fewer than `inbits` and all zero.
+fn convert_bits(
+    data: &[u8],
+    inbits: u8,
+    outbits: u8,
+    pad: bool,
+    ret: &mut Vec<u8>,
+) -> Result<(), DecodingError> {
     assert!(inbits <= 8 && outbits <= 8);
-    let num_bytes = (data.len() * inbits as usize + outbits as usize - 1) / outbits as usize;
-    let mut ret = Vec::with_capacity(num_bytes);
+    let num_bytes = (data.len() * inbits as usize).div_ceil(outbits as usize);
+    ret.clear();
+    ret.reserve(num_bytes);
     let mut acc: u16 = 0; // accumulator of bits
     let mut num: u8 = 0; // num bits in acc
     let groupmask = (1 << outbits) - 1;
@@ -111,165 +158,337 @@ fn convert_bits(data: &[u8], inbits: u8, outbits: u8, pad: bool) -> Vec<u8> {
         if num as usize > padding {
             ret.push((acc >> padding) as u8);
         }
+        // Padding must not span a whole input group and must be zero
+        if padding >= inbits as usize || acc & ((1 << padding) - 1) != 0 {
+            return Err(DecodingError::InvalidPadding);
+        }
     }
-    ret
+    Ok(())
+}
+
+// Buffers reused across decoding calls.
+#[derive(Default)]
+struct DecodeBuffers {
+    payload_5_bits: Vec<u8>,
+    payload: Vec<u8>,
 }
 
 /// Codec allowing the encoding and decoding of CashAddrs.
 pub struct CashAddrCodec;
 
-impl AddressCodec for CashAddrCodec {
-    type EncodingError = EncodingError;
-    type DecodingError = DecodingError;
+impl CashAddrCodec {
+    /// Attempt to convert the raw address bytes to a cashaddr string without
+    /// the network prefix.
+    pub fn encode_without_prefix(
+        raw: &[u8],
+        hash_type: HashType,
+        network: Network,
+    ) -> Result<String, EncodingError> {
+        let mut payload_str = String::with_capacity(encoded_len(raw.len()));
+        Self::write_cashaddr(
+            raw,
+            &hash_type,
+            network_prefix(&network).map_err(EncodingError::InvalidPrefix)?,
+            false,
+            false,
+            &mut payload_str,
+        )?;
+        Ok(payload_str)
+    }
 
-    fn encode(
+    /// Attempt to convert the raw address bytes to an uppercase cashaddr string, which
+    /// encodes more compactly in QR codes.
+    pub fn encode_uppercase(
         raw: &[u8],
         hash_type: HashType,
         network: Network,
-    ) -> Result<String, Self::EncodingError> {
-        // Calculate version byte
-        let hash_flag = match hash_type {
-            HashType::Key => version_byte_flags::TYPE_P2PKH,
-            HashType::Script => version_byte_flags::TYPE_P2SH,
-        };
-        let length = raw.len();
-        let version_byte = match length {
-            20 => version_byte_flags::SIZE_160,
-            24 => version_byte_flags::SIZE_192,
-            28 => version_byte_flags::SIZE_224,
-            32 => version_byte_flags::SIZE_256,
-            40 => version_byte_flags::SIZE_320,
-            48 => version_byte_flags::SIZE_384,
-            56 => version_byte_flags::SIZE_448,
-            64 => version_byte_flags::SIZE_512,
-            _ => return Err(EncodingError(length)),
-        } | hash_flag;
-
-        // Get prefix
-        let prefix = match network {
-            Network::Main => MAINNET_PREFIX,
-            Network::Test => TESTNET_PREFIX,
-            Network::Regtest => REGNET_PREFIX,
-        };
+    ) -> Result<String, EncodingError> {
+        let prefix = network_prefix(&network).map_err(EncodingError::InvalidPrefix)?;
+        let mut cashaddr = String::with_capacity(prefix.len() + 1 + encoded_len(raw.len()));
+        Self::write_cashaddr(raw, &hash_type, prefix, true, true, &mut cashaddr)?;
+        Ok(cashaddr)
+    }
+
+    /// Attempt to write the cashaddr of the raw address bytes to `w`, without allocating.
+    pub fn encode_to_fmt<W: fmt::Write>(
+        raw: &[u8],
+        hash_type: &HashType,
+        network: &Network,
+        w: &mut W,
+    ) -> Result<(), EncodingError> {
+        let prefix = network_prefix(network).map_err(EncodingError::InvalidPrefix)?;
+        Self::write_cashaddr(raw, hash_type, prefix, true, false, w)
+    }
 
-        // Convert payload to 5 bit array
-        let mut payload = Vec::with_capacity(1 + raw.len());
-        payload.push(version_byte);
-        payload.extend(raw);
-        let payload_5_bits = convert_bits(&payload, 8, 5, true);
+    /// Attempt to write the cashaddr of the raw address bytes into `buf`, without allocating.
+    /// Returns the number of bytes written.
+    pub fn encode_to_slice(
+        raw: &[u8],
+        hash_type: &HashType,
+        network: &Network,
+        buf: &mut [u8],
+    ) -> Result<usize, EncodingError> {
+        let mut writer = SliceWriter::new(buf);
+        match Self::encode_to_fmt(raw, hash_type, network, &mut writer) {
+            Ok(()) => Ok(writer.len()),
+            Err(EncodingError::Fmt(_)) => Err(EncodingError::BufferTooSmall),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Attempt to convert a cashaddr string to bytes, additionally rejecting addresses which
+    /// do not correspond to a standard locking script.
+    ///
+    /// Only 20-byte public key hashes and 20 or 32-byte script hashes, including their
+    /// token-aware forms, are accepted.
+    pub fn decode_strict(addr_str: &str) -> Result<Address, DecodingError> {
+        let addr = Self::decode(addr_str)?;
+        if !addr.is_standard() {
+            return Err(DecodingError::NonStandard {
+                hash_type: addr.hash_type,
+                length: addr.body.len(),
+            });
+        }
+        Ok(addr)
+    }
+
+    /// Attempt to convert a cashaddr string to bytes, accepting address types this version
+    /// does not recognise as `HashType::Unknown`.
+    ///
+    /// Such addresses re-encode unchanged, allowing them to be passed through by services
+    /// which predate the upgrade introducing the type.
+    pub fn decode_lenient(addr_str: &str) -> Result<Address, DecodingError> {
+        let (prefix, payload_str) = split_prefix(addr_str)?;
+        let network = prefix_network(&prefix);
 
-        // Construct payload string using CHARSET
-        let payload_str: String = payload_5_bits
+        Self::decode_payload(payload_str, &prefix, network, true)
+    }
+
+    /// Check whether each cashaddr string would decode successfully, amortising allocations
+    /// and prefix checksum computations across the batch.
+    pub fn validate_many(addrs: &[&str]) -> Vec<Result<(), DecodingError>> {
+        let mut buffers = DecodeBuffers::default();
+        addrs
             .iter()
-            .map(|b| CHARSET[*b as usize] as char)
-            .collect();
+            .map(|addr_str| {
+                let (prefix, payload_str) = split_prefix(addr_str)?;
+                Self::decode_into(payload_str, &prefix, false, &mut buffers).map(|_| ())
+            })
+            .collect()
+    }
 
-        // Create checksum
-        let expanded_prefix = expand_prefix(prefix);
-        let checksum_input = [&expanded_prefix[..], &payload_5_bits, &[0; 8][..]].concat();
-        let checksum = polymod(&checksum_input);
+    /// Attempt to convert a cashaddr string, which may omit the prefix, to bytes.
+    ///
+    /// If the prefix is omitted, the checksum is tested against the mainnet, testnet
+    /// and regtest prefixes followed by the prefixes of `networks`, and the network
+    /// whose checksum validates is returned.
+    pub fn decode_infer_network(
+        addr_str: &str,
+        networks: &[Network],
+    ) -> Result<Address, DecodingError> {
+        if addr_str.contains(':') {
+            return Self::decode(addr_str);
+        }
 
-        // Convert checksum to string
-        let checksum_str: String = (0..8)
-            .rev()
-            .map(|i| CHARSET[((checksum >> (i * 5)) & 31) as usize] as char)
-            .collect();
+        let candidates = [Network::Main, Network::Test, Network::Regtest];
+        let mut matches = Vec::with_capacity(1);
+        for network in candidates.iter().chain(networks) {
+            let prefix = network_prefix(network).map_err(DecodingError::InvalidPrefix)?;
+            if !is_valid_prefix(prefix) {
+                return Err(DecodingError::InvalidPrefix(prefix.to_string()));
+            }
+            if matches
+                .iter()
+                .any(|addr: &Address| addr.network == *network)
+            {
+                continue;
+            }
+            match Self::decode_payload(addr_str, prefix, network.to_owned(), false) {
+                Ok(addr) => matches.push(addr),
+                Err(DecodingError::ChecksumFailed(_)) => continue,
+                Err(err) => return Err(err),
+            }
+        }
 
-        // Concatentate all parts
-        let cashaddr = [prefix, ":", &payload_str, &checksum_str].concat();
-        Ok(cashaddr)
+        match matches.len() {
+            0 => Err(DecodingError::NoMatchingPrefix),
+            1 => Ok(matches.remove(0)),
+            _ => Err(DecodingError::AmbiguousPrefix(
+                matches.into_iter().map(|addr| addr.network).collect(),
+            )),
+        }
     }
 
-    fn decode(addr_str: &str) -> Result<Address, Self::DecodingError> {
-        // Delimit and extract prefix
-        let parts: Vec<&str> = addr_str.split(':').collect();
-        if parts.len() != 2 {
-            return Err(DecodingError::NoPrefix);
+    // Write the cashaddr, optionally excluding the prefix, without allocating.
+    fn write_cashaddr<W: fmt::Write>(
+        raw: &[u8],
+        hash_type: &HashType,
+        prefix: &str,
+        with_prefix: bool,
+        uppercase: bool,
+        w: &mut W,
+    ) -> Result<(), EncodingError> {
+        // Calculate version byte
+        let version_byte = VersionByte::from_hash_type(hash_type, raw.len())?.to_byte();
+
+        // Check prefix
+        if !is_valid_prefix(prefix) {
+            return Err(EncodingError::InvalidPrefix(prefix.to_string()));
+        }
+        let charset = if uppercase { CHARSET_UPPER } else { CHARSET };
+        if with_prefix {
+            for c in prefix.chars() {
+                w.write_char(if uppercase { c.to_ascii_uppercase() } else { c })?;
+            }
+            w.write_char(':')?;
         }
-        let prefix = parts[0];
-        let payload_str = parts[1];
-
-        // Match network
-        let network = match prefix {
-            MAINNET_PREFIX => Network::Main,
-            TESTNET_PREFIX => Network::Test,
-            REGNET_PREFIX => Network::Regtest,
-            _ => return Err(DecodingError::InvalidPrefix(prefix.to_string())),
-        };
 
-        // Do some sanity checks on the string
-        let mut payload_chars = payload_str.chars();
-        if let Some(first_char) = payload_chars.next() {
-            if first_char.is_lowercase() {
-                if payload_chars.any(|c| c.is_uppercase()) {
-                    return Err(DecodingError::MixedCase);
-                }
-            } else if payload_chars.any(|c| c.is_lowercase()) {
-                return Err(DecodingError::MixedCase);
+        // Start from the checksum state of the expanded prefix
+        let mut checksum = prefix_state(prefix);
+
+        // Convert payload to 5 bit groups, writing each using CHARSET
+        let mut acc: u32 = 0; // accumulator of bits
+        let mut num: u8 = 0; // num bits in acc
+        for byte in iter::once(version_byte).chain(raw.iter().copied()) {
+            acc = (acc << 8) | u32::from(byte);
+            num += 8;
+            while num >= 5 {
+                num -= 5;
+                let d = ((acc >> num) & 31) as u8;
+                checksum = polymod_step(checksum, d);
+                w.write_char(charset[d as usize] as char)?;
             }
-        } else {
+        }
+        if num > 0 {
+            let d = ((acc << (5 - num)) & 31) as u8;
+            checksum = polymod_step(checksum, d);
+            w.write_char(charset[d as usize] as char)?;
+        }
+
+        // Finalize and write checksum
+        checksum = polymod_update(checksum, &[0; 8]) ^ 1;
+        for i in (0..8).rev() {
+            w.write_char(charset[((checksum >> (i * 5)) & 31) as usize] as char)?;
+        }
+        Ok(())
+    }
+
+    // Convert the payload string to a 5 bit array.
+    fn payload_to_5_bits(payload_str: &str, ret: &mut Vec<u8>) -> Result<(), DecodingError> {
+        // Do some sanity checks on the string
+        if payload_str.is_empty() {
             return Err(DecodingError::InvalidLength(0));
         }
+        if is_mixed_case(payload_str) {
+            return Err(DecodingError::MixedCase);
+        }
 
         // Decode payload to 5 bit array
-        let payload_chars = payload_str.chars(); // Reintialize iterator here
-        let payload_5_bits: Result<Vec<u8>, DecodingError> = payload_chars
-            .map(|c| {
-                let i = c as usize;
-                if let Some(Some(d)) = CHARSET_REV.get(i) {
-                    Ok(*d as u8)
-                } else {
-                    Err(DecodingError::InvalidChar(c))
-                }
-            })
-            .collect();
-        let payload_5_bits = payload_5_bits?;
+        ret.clear();
+        for c in payload_str.chars() {
+            let i = c as usize;
+            if let Some(Some(d)) = CHARSET_REV.get(i) {
+                ret.push(*d);
+            } else {
+                return Err(DecodingError::InvalidChar(c));
+            }
+        }
+        Ok(())
+    }
+
+    // Decode the payload and verify its checksum against the prefix.
+    fn decode_payload(
+        payload_str: &str,
+        prefix: &str,
+        network: Network,
+        lenient: bool,
+    ) -> Result<Address, DecodingError> {
+        let mut buffers = DecodeBuffers::default();
+        let hash_type = Self::decode_into(payload_str, prefix, lenient, &mut buffers)?;
+        Ok(Address {
+            scheme: Scheme::CashAddr,
+            body: buffers.payload[1..].to_vec(),
+            hash_type,
+            network,
+        })
+    }
+
+    // Decode the payload into `buffers` and verify its checksum against the prefix,
+    // returning the hash type. The version byte and body are left in `buffers.payload`.
+    // Unless `lenient`, unknown address types are rejected.
+    fn decode_into(
+        payload_str: &str,
+        prefix: &str,
+        lenient: bool,
+        buffers: &mut DecodeBuffers,
+    ) -> Result<HashType, DecodingError> {
+        Self::payload_to_5_bits(payload_str, &mut buffers.payload_5_bits)?;
+        let payload_5_bits = &buffers.payload_5_bits;
+
+        // Check there is room for the version byte and checksum
+        let len_5_bit = payload_5_bits.len();
+        if len_5_bit < MIN_PAYLOAD_LEN {
+            return Err(DecodingError::TooShort(len_5_bit));
+        }
 
         // Verify the checksum
-        let checksum = polymod(&[&expand_prefix(prefix), &payload_5_bits[..]].concat());
+        let checksum = polymod(prefix, payload_5_bits);
         if checksum != 0 {
             return Err(DecodingError::ChecksumFailed(checksum));
         }
 
         // Convert from 5 bit array to byte array
-        let len_5_bit = payload_5_bits.len();
-        let payload = convert_bits(&payload_5_bits[..(len_5_bit - 8)], 5, 8, false);
+        convert_bits(
+            &payload_5_bits[..(len_5_bit - 8)],
+            5,
+            8,
+            false,
+            &mut buffers.payload,
+        )?;
+        let payload = &buffers.payload;
 
         // Verify the version byte
-        let version = payload[0];
+        let version = VersionByte::from_byte(payload[0])?;
 
         // Check length
-        let body = &payload[1..];
-        let body_len = body.len();
-        let version_size = version & version_byte_flags::SIZE_MASK;
-        if (version_size == version_byte_flags::SIZE_160 && body_len != 20)
-            || (version_size == version_byte_flags::SIZE_192 && body_len != 24)
-            || (version_size == version_byte_flags::SIZE_224 && body_len != 28)
-            || (version_size == version_byte_flags::SIZE_256 && body_len != 32)
-            || (version_size == version_byte_flags::SIZE_320 && body_len != 40)
-            || (version_size == version_byte_flags::SIZE_384 && body_len != 48)
-            || (version_size == version_byte_flags::SIZE_448 && body_len != 56)
-            || (version_size == version_byte_flags::SIZE_512 && body_len != 64)
-        {
+        let body_len = payload.len() - 1;
+        if body_len != version.hash_len() {
             return Err(DecodingError::InvalidLength(body_len));
         }
 
         // Extract the hash type and return
-        let version_type = version & version_byte_flags::TYPE_MASK;
-        let hash_type = if version_type == version_byte_flags::TYPE_P2PKH {
-            HashType::Key
-        } else if version_type == version_byte_flags::TYPE_P2SH {
-            HashType::Script
+        let hash_type = if lenient {
+            version.hash_type_lenient()
         } else {
-            return Err(DecodingError::InvalidVersion(version));
+            version
+                .hash_type()
+                .ok_or(DecodingError::InvalidVersion(version.to_byte()))?
         };
 
-        Ok(Address {
-            scheme: Scheme::CashAddr,
-            body: body.to_vec(),
-            hash_type,
-            network,
-        })
+        Ok(hash_type)
+    }
+}
+
+impl AddressCodec for CashAddrCodec {
+    type EncodingError = EncodingError;
+    type DecodingError = DecodingError;
+
+    fn encode(
+        raw: &[u8],
+        hash_type: HashType,
+        network: Network,
+    ) -> Result<String, Self::EncodingError> {
+        let prefix = network_prefix(&network).map_err(EncodingError::InvalidPrefix)?;
+        let mut cashaddr = String::with_capacity(prefix.len() + 1 + encoded_len(raw.len()));
+        Self::write_cashaddr(raw, &hash_type, prefix, true, false, &mut cashaddr)?;
+        Ok(cashaddr)
+    }
+
+    fn decode(addr_str: &str) -> Result<Address, Self::DecodingError> {
+        let (prefix, payload_str) = split_prefix(addr_str)?;
+        let network = prefix_network(&prefix);
+
+        Self::decode_payload(payload_str, &prefix, network, false)
     }
 }
 
@@ -358,6 +577,413 @@ mod tests {
         );
     }
 
+    #[test]
+    fn custom_prefixes() {
+        // 20-byte public key hash under non-standard prefixes
+        let data = hex::decode("F5BF48B397DAE70BE82B3CCA4793F8EB2B6CDAC9").unwrap();
+        verify(
+            Network::Other("ecash".to_string()),
+            &data,
+            "ecash:qr6m7j9njldwwzlg9v7v53unlr4jkmx6eyx54vzvwa",
+        );
+        verify(
+            Network::Other("simpleledger".to_string()),
+            &data,
+            "simpleledger:qr6m7j9njldwwzlg9v7v53unlr4jkmx6eynz2uvkk5",
+        );
+        verify(
+            Network::Other("etoken".to_string()),
+            &data,
+            "etoken:qr6m7j9njldwwzlg9v7v53unlr4jkmx6eyg2uw5t22",
+        );
+
+        let decoded =
+            CashAddrCodec::decode("ecash:qr6m7j9njldwwzlg9v7v53unlr4jkmx6eyx54vzvwa").unwrap();
+        assert_eq!(decoded.network, Network::Other("ecash".to_string()));
+        let decoded =
+            CashAddrCodec::decode("bchtest:qr6m7j9njldwwzlg9v7v53unlr4jkmx6eymt9qmp0k").unwrap();
+        assert_eq!(decoded.network, Network::Test);
+    }
+
+    #[test]
+    fn invalid_prefixes() {
+        let data = hex::decode("F5BF48B397DAE70BE82B3CCA4793F8EB2B6CDAC9").unwrap();
+        for prefix in &[
+            "",
+            "eCash",
+            "e-cash",
+            "ecash ",
+            "bitcoincash",
+            "bchtest",
+            "bchreg",
+        ] {
+            let network = Network::Other(prefix.to_string());
+            assert_eq!(
+                CashAddrCodec::encode(&data, HashType::Key, network),
+                Err(EncodingError::InvalidPrefix(prefix.to_string()))
+            );
+        }
+        assert_eq!(
+            CashAddrCodec::decode(":qr6m7j9njldwwzlg9v7v53unlr4jkmx6eyx54vzvwa"),
+            Err(DecodingError::InvalidPrefix("".to_string()))
+        );
+        assert_eq!(
+            CashAddrCodec::decode("e-cash:qr6m7j9njldwwzlg9v7v53unlr4jkmx6eyx54vzvwa"),
+            Err(DecodingError::InvalidPrefix("e-cash".to_string()))
+        );
+
+        // Built-in prefixes would not round trip as other networks
+        let mainnet = Network::Other("bitcoincash".to_string());
+        assert_eq!(
+            CashAddrCodec::decode_infer_network(
+                "qr6m7j9njldwwzlg9v7v53unlr4jkmx6eylep8ekg2",
+                &[mainnet]
+            ),
+            Err(DecodingError::InvalidPrefix("bitcoincash".to_string()))
+        );
+    }
+
+    #[test]
+    fn prefixless() {
+        let data = hex::decode("F5BF48B397DAE70BE82B3CCA4793F8EB2B6CDAC9").unwrap();
+        let ecash = Network::Other("ecash".to_string());
+        let custom = [ecash.clone()];
+        let cases = [
+            (Network::Main, "qr6m7j9njldwwzlg9v7v53unlr4jkmx6eylep8ekg2"),
+            (Network::Test, "qr6m7j9njldwwzlg9v7v53unlr4jkmx6eymt9qmp0k"),
+            (ecash.clone(), "qr6m7j9njldwwzlg9v7v53unlr4jkmx6eyx54vzvwa"),
+        ];
+        for (network, payload) in cases.iter() {
+            let output =
+                CashAddrCodec::encode_without_prefix(&data, HashType::Key, network.clone())
+                    .unwrap();
+            assert_eq!(output, *payload);
+            let decoded = CashAddrCodec::decode_infer_network(payload, &custom).unwrap();
+            assert_eq!(decoded.network, *network);
+            assert_eq!(decoded.body, data);
+        }
+
+        // Custom prefixes are only tried when configured
+        assert_eq!(
+            CashAddrCodec::decode_infer_network("qr6m7j9njldwwzlg9v7v53unlr4jkmx6eyx54vzvwa", &[]),
+            Err(DecodingError::NoMatchingPrefix)
+        );
+
+        // Prefixed addresses are decoded as usual
+        let decoded = CashAddrCodec::decode_infer_network(
+            "bitcoincash:qr6m7j9njldwwzlg9v7v53unlr4jkmx6eylep8ekg2",
+            &[],
+        )
+        .unwrap();
+        assert_eq!(decoded.network, Network::Main);
+    }
+
+    #[test]
+    fn token_aware() {
+        // CashTokens specification test vector
+        let plain_str = "bitcoincash:qr7fzmep8g7h7ymfxy74lgc0v950j3r2959lhtxxsl";
+        let token_str = "bitcoincash:zr7fzmep8g7h7ymfxy74lgc0v950j3r295z4y4gq0v";
+        let token_addr = CashAddrCodec::decode(token_str).unwrap();
+        assert_eq!(token_addr.hash_type, HashType::TokenKey);
+        assert_eq!(
+            hex::encode(token_addr.as_body()),
+            "fc916f213a3d7f1369313d5fa30f6168f9446a2d"
+        );
+        assert_eq!(token_addr.encode().unwrap(), token_str);
+
+        // Convert between token-aware and plain forms
+        let plain_addr = token_addr.into_plain();
+        assert_eq!(plain_addr.hash_type, HashType::Key);
+        assert_eq!(plain_addr.encode().unwrap(), plain_str);
+        assert_eq!(plain_addr.into_token_aware().encode().unwrap(), token_str);
+
+        // Token-aware script hash
+        let data = hex::decode("F5BF48B397DAE70BE82B3CCA4793F8EB2B6CDAC9").unwrap();
+        let output = CashAddrCodec::encode(&data, HashType::TokenScript, Network::Main).unwrap();
+        assert_eq!(
+            output,
+            "bitcoincash:rr6m7j9njldwwzlg9v7v53unlr4jkmx6ey0k0ksnvy"
+        );
+        let decoded = CashAddrCodec::decode(&output).unwrap();
+        assert_eq!(decoded.hash_type, HashType::TokenScript);
+    }
+
+    #[test]
+    fn p2sh32() {
+        // OP_1 redeem script
+        let address = Address::from_redeem_script_p2sh32(&[0x51], Network::Main);
+        assert!(address.is_p2sh32());
+        assert!(!address.is_p2sh20());
+        assert_eq!(
+            hex::encode(address.as_body()),
+            "953ccfa596a6c6d39e5980194539124fdcff116a571455a212baed811f585ee0"
+        );
+        let output = address.encode().unwrap();
+        assert_eq!(
+            output,
+            "bitcoincash:pw2nena9j6nvd5u7txqpj3fezf8aelc3dft3g4dzz2awmqgltp0wq69r7fk66"
+        );
+        let decoded = CashAddrCodec::decode(&output).unwrap();
+        assert_eq!(decoded, address);
+        assert!(decoded.is_p2sh32());
+
+        let token_aware = decoded.into_token_aware();
+        assert!(token_aware.is_p2sh32());
+        assert_eq!(
+            token_aware.encode().unwrap(),
+            "bitcoincash:rw2nena9j6nvd5u7txqpj3fezf8aelc3dft3g4dzz2awmqgltp0wqgkllshr3"
+        );
+
+        // Converting to base58 is refused
+        let mut legacy = address;
+        legacy.scheme = Scheme::Base58;
+        assert_eq!(
+            legacy.encode(),
+            Err(crate::EncodingError::Base58(
+                base58::EncodingError::InvalidLength(32)
+            ))
+        );
+    }
+
+    #[test]
+    fn encode_to_buffers() {
+        let data = hex::decode("F5BF48B397DAE70BE82B3CCA4793F8EB2B6CDAC9").unwrap();
+        let expected = "bitcoincash:qr6m7j9njldwwzlg9v7v53unlr4jkmx6eylep8ekg2";
+
+        let mut buf = [0u8; 64];
+        let len = CashAddrCodec::encode_to_slice(&data, &HashType::Key, &Network::Main, &mut buf)
+            .unwrap();
+        assert_eq!(&buf[..len], expected.as_bytes());
+
+        let mut small = [0u8; 32];
+        assert_eq!(
+            CashAddrCodec::encode_to_slice(&data, &HashType::Key, &Network::Main, &mut small),
+            Err(EncodingError::BufferTooSmall)
+        );
+
+        let mut output = String::new();
+        CashAddrCodec::encode_to_fmt(&data, &HashType::Key, &Network::Main, &mut output).unwrap();
+        assert_eq!(output, expected);
+
+        let address = Address::new(data, Scheme::CashAddr, HashType::Key, Network::Main);
+        assert_eq!(address.to_string(), expected);
+    }
+
+    #[test]
+    fn uppercase() {
+        let data = hex::decode("F5BF48B397DAE70BE82B3CCA4793F8EB2B6CDAC9").unwrap();
+        let expected = "BITCOINCASH:QR6M7J9NJLDWWZLG9V7V53UNLR4JKMX6EYLEP8EKG2";
+        let output = CashAddrCodec::encode_uppercase(&data, HashType::Key, Network::Main).unwrap();
+        assert_eq!(output, expected);
+        let decoded = CashAddrCodec::decode(expected).unwrap();
+        assert_eq!(decoded.network, Network::Main);
+        assert_eq!(decoded.body, data);
+
+        let ecash = Network::Other("ecash".to_string());
+        let output = CashAddrCodec::encode_uppercase(&data, HashType::Key, ecash.clone()).unwrap();
+        assert_eq!(output, "ECASH:QR6M7J9NJLDWWZLG9V7V53UNLR4JKMX6EYX54VZVWA");
+        assert_eq!(CashAddrCodec::decode(&output).unwrap().network, ecash);
+
+        // The whole string must be single case
+        for addr in &[
+            "bitcoincash:QR6M7J9NJLDWWZLG9V7V53UNLR4JKMX6EYLEP8EKG2",
+            "BITCOINCASH:qr6m7j9njldwwzlg9v7v53unlr4jkmx6eylep8ekg2",
+            "BitcoinCash:qr6m7j9njldwwzlg9v7v53unlr4jkmx6eylep8ekg2",
+            "bitcoincash:qr6m7j9njldwwzlg9v7v53unlr4jkmx6eylep8ekG2",
+        ] {
+            assert_eq!(CashAddrCodec::decode(addr), Err(DecodingError::MixedCase));
+        }
+    }
+
+    #[test]
+    fn strict() {
+        let standard = [
+            "bitcoincash:qr6m7j9njldwwzlg9v7v53unlr4jkmx6eylep8ekg2",
+            "bitcoincash:zr7fzmep8g7h7ymfxy74lgc0v950j3r295z4y4gq0v",
+            "bitcoincash:pw2nena9j6nvd5u7txqpj3fezf8aelc3dft3g4dzz2awmqgltp0wq69r7fk66",
+            "bitcoincash:rw2nena9j6nvd5u7txqpj3fezf8aelc3dft3g4dzz2awmqgltp0wqgkllshr3",
+        ];
+        for addr in standard.iter() {
+            assert_eq!(
+                CashAddrCodec::decode_strict(addr),
+                CashAddrCodec::decode(addr)
+            );
+        }
+
+        // 32 and 64-byte public key hashes
+        let non_standard = [
+            (
+                "bitcoincash:qvch8mmxy0rtfrlarg7ucrxxfzds5pamg73h7370aa87d80gyhqxq5nlegake",
+                32,
+            ),
+            ("bitcoincash:qlg0x333p4238k0qrc5ej7rzfw5g8e4a4r6vvzyrcy8j3s5k0en7calvclhw46hudk5flttj6ydvjc0pv3nchp52amk97tqa5zygg96mtky5sv5w", 64),
+        ];
+        for (addr, length) in non_standard.iter() {
+            assert!(CashAddrCodec::decode(addr).is_ok());
+            assert_eq!(
+                CashAddrCodec::decode_strict(addr),
+                Err(DecodingError::NonStandard {
+                    hash_type: HashType::Key,
+                    length: *length
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn validate_many() {
+        let addrs = [
+            "bitcoincash:qr6m7j9njldwwzlg9v7v53unlr4jkmx6eylep8ekg2",
+            "BCHTEST:QR6M7J9NJLDWWZLG9V7V53UNLR4JKMX6EYMT9QMP0K",
+            "ecash:qr6m7j9njldwwzlg9v7v53unlr4jkmx6eyx54vzvwa",
+            "bitcoincash:qr6m7j9njldwwzlg9v7v53unlr4jkmx6eylep8ekg3",
+            "qr6m7j9njldwwzlg9v7v53unlr4jkmx6eylep8ekg2",
+            "bitcoincash:qr6m7j9njldwwzlg9v7v53unlr4jkmx6eylep8ekgb",
+        ];
+        let expected: Vec<_> = addrs
+            .iter()
+            .map(|addr| CashAddrCodec::decode(addr).map(|_| ()))
+            .collect();
+        assert_eq!(CashAddrCodec::validate_many(&addrs), expected);
+        assert_eq!(expected.iter().filter(|result| result.is_ok()).count(), 3);
+    }
+
+    // Build a payload string over the 5 bit data with a valid checksum for the prefix
+    fn with_checksum(prefix: &str, data: &[u8]) -> String {
+        let checksum = polymod_update(prefix_state(prefix), &[data, &[0; 8]].concat()) ^ 1;
+        data.iter()
+            .copied()
+            .chain((0..8).rev().map(|i| ((checksum >> (i * 5)) & 31) as u8))
+            .map(|d| CHARSET[d as usize] as char)
+            .collect()
+    }
+
+    // Exercise every decoding path, which must not panic
+    fn decode_all(addr_str: &str) -> Result<Address, DecodingError> {
+        let _ = CashAddrCodec::decode_infer_network(addr_str, &[]);
+        let _ = CashAddrCodec::decode_strict(addr_str);
+        let _ = CashAddrCodec::decode_lenient(addr_str);
+        let _ = CashAddrCodec::locate_errors(addr_str);
+        let _ = CashAddrCodec::validate_many(&[addr_str]);
+        let _ = Address::decode(addr_str);
+        CashAddrCodec::decode(addr_str)
+    }
+
+    #[test]
+    fn short_payloads() {
+        // Valid checksums over too few symbols to hold a version byte
+        for data_len in 0..2 {
+            for d in 0..32 {
+                let data = vec![d; data_len];
+                let payload_str = with_checksum(MAINNET_PREFIX, &data);
+                let addr_str = [MAINNET_PREFIX, ":", &payload_str].concat();
+                let expected = Err(DecodingError::TooShort(8 + data_len));
+                assert_eq!(decode_all(&addr_str), expected);
+                assert_eq!(
+                    CashAddrCodec::decode_infer_network(&payload_str, &[]),
+                    expected
+                );
+                assert_eq!(
+                    CashAddrCodec::validate_many(&[&addr_str]),
+                    vec![Err(DecodingError::TooShort(8 + data_len))]
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn padding() {
+        let addr_str = "bitcoincash:qr6m7j9njldwwzlg9v7v53unlr4jkmx6eylep8ekg2";
+        let mut data = Vec::new();
+        CashAddrCodec::payload_to_5_bits(&addr_str[12..46], &mut data).unwrap();
+        let payload_str = with_checksum(MAINNET_PREFIX, &data);
+        assert_eq!(&addr_str[12..], payload_str);
+
+        // The 34 symbols of a 20-byte address carry 2 bits of padding
+        for bits in 1..4 {
+            let mut malleated = data.clone();
+            malleated[33] ^= bits;
+            let malleated_str = [
+                MAINNET_PREFIX,
+                ":",
+                &with_checksum(MAINNET_PREFIX, &malleated),
+            ]
+            .concat();
+            assert_eq!(
+                CashAddrCodec::decode(&malleated_str),
+                Err(DecodingError::InvalidPadding)
+            );
+        }
+
+        // An extra symbol is excess padding
+        let mut extended = data.clone();
+        extended.push(0);
+        let extended_str = [
+            MAINNET_PREFIX,
+            ":",
+            &with_checksum(MAINNET_PREFIX, &extended),
+        ]
+        .concat();
+        assert_eq!(
+            CashAddrCodec::decode(&extended_str),
+            Err(DecodingError::InvalidPadding)
+        );
+    }
+
+    #[test]
+    fn unknown_types() {
+        let data = hex::decode("F5BF48B397DAE70BE82B3CCA4793F8EB2B6CDAC9").unwrap();
+        let hash_type = HashType::Unknown(7);
+        let output = CashAddrCodec::encode(&data, hash_type.clone(), Network::Main).unwrap();
+        assert_eq!(
+            CashAddrCodec::decode(&output),
+            Err(DecodingError::InvalidVersion(0x38))
+        );
+
+        let decoded = CashAddrCodec::decode_lenient(&output).unwrap();
+        assert_eq!(decoded.hash_type, hash_type);
+        assert_eq!(decoded.body, data);
+        assert!(!decoded.is_standard());
+        assert_eq!(decoded.encode().unwrap(), output);
+
+        // Known types decode as usual
+        let known = "bitcoincash:qr6m7j9njldwwzlg9v7v53unlr4jkmx6eylep8ekg2";
+        assert_eq!(
+            CashAddrCodec::decode_lenient(known),
+            CashAddrCodec::decode(known)
+        );
+        assert_eq!(
+            CashAddrCodec::encode(&data, HashType::Unknown(1), Network::Main),
+            Err(EncodingError::InvalidType(1))
+        );
+    }
+
+    #[test]
+    fn reserved_version_bit() {
+        let addr_str = "bitcoincash:qr6m7j9njldwwzlg9v7v53unlr4jkmx6eylep8ekg2";
+        let mut data = Vec::new();
+        CashAddrCodec::payload_to_5_bits(&addr_str[12..46], &mut data).unwrap();
+
+        // The top bit of the first symbol is the reserved bit of the version byte
+        data[0] ^= 0x10;
+        let reserved_str = [MAINNET_PREFIX, ":", &with_checksum(MAINNET_PREFIX, &data)].concat();
+        assert_eq!(
+            decode_all(&reserved_str),
+            Err(DecodingError::InvalidVersion(0x80))
+        );
+    }
+
+    #[test]
+    fn exhaustive_short_inputs() {
+        let alphabet = [
+            'q', 'p', 'l', '0', ':', 'Q', 'b', '\u{7f}', '\u{80}', 'é', ' ',
+        ];
+        for input in crate::short_strings(&alphabet, 4).iter() {
+            assert!(decode_all(input).is_err());
+            assert!(decode_all(&[MAINNET_PREFIX, ":", input].concat()).is_err());
+        }
+    }
+
     fn verify(network: Network, data: &Vec<u8>, cashaddr: &str) {
         let hash_type = HashType::Key;
         let output = CashAddrCodec::encode(data, hash_type, network).unwrap();
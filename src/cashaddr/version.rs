@@ -0,0 +1,155 @@
+use super::*;
+
+// Hash lengths indexed by the size bits
+const HASH_LENGTHS: [usize; 8] = [20, 24, 28, 32, 40, 48, 56, 64];
+
+/// CashAddr version byte, holding the address type and hash size.
+///
+/// The high bit is reserved and must be zero, the next four bits hold the address
+/// type and the low three bits encode the length of the hash.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Hash)]
+pub struct VersionByte(u8);
+
+impl VersionByte {
+    /// Reserved bit, which must be zero.
+    pub const RESERVED_MASK: u8 = 0x80;
+    /// Address type bits.
+    pub const TYPE_MASK: u8 = 0x78;
+    /// Hash size bits.
+    pub const SIZE_MASK: u8 = 0x07;
+
+    /// Public key hash address type.
+    pub const TYPE_P2PKH: u8 = 0;
+    /// Script hash address type.
+    pub const TYPE_P2SH: u8 = 1;
+    /// Token-aware public key hash address type.
+    pub const TYPE_TOKEN_P2PKH: u8 = 2;
+    /// Token-aware script hash address type.
+    pub const TYPE_TOKEN_P2SH: u8 = 3;
+
+    /// Construct a version byte from an address type (0 to 15) and hash length.
+    /// Returns `None` if either is out of range.
+    pub fn new(address_type: u8, hash_len: usize) -> Option<Self> {
+        if address_type > Self::TYPE_MASK >> 3 {
+            return None;
+        }
+        let size = HASH_LENGTHS.iter().position(|len| *len == hash_len)? as u8;
+        Some(VersionByte((address_type << 3) | size))
+    }
+
+    /// Construct the version byte of a hash type and hash length.
+    ///
+    /// `HashType::Unknown` must carry an address type which is in range and not already
+    /// represented by another hash type.
+    pub fn from_hash_type(hash_type: &HashType, hash_len: usize) -> Result<Self, EncodingError> {
+        let address_type = match hash_type {
+            HashType::Key => Self::TYPE_P2PKH,
+            HashType::Script => Self::TYPE_P2SH,
+            HashType::TokenKey => Self::TYPE_TOKEN_P2PKH,
+            HashType::TokenScript => Self::TYPE_TOKEN_P2SH,
+            HashType::Unknown(address_type) => {
+                if *address_type <= Self::TYPE_TOKEN_P2SH || *address_type > Self::TYPE_MASK >> 3 {
+                    return Err(EncodingError::InvalidType(*address_type));
+                }
+                *address_type
+            }
+        };
+        Self::new(address_type, hash_len).ok_or(EncodingError::InvalidLength(hash_len))
+    }
+
+    /// Parse a version byte, rejecting it if the reserved bit is set.
+    pub fn from_byte(byte: u8) -> Result<Self, DecodingError> {
+        if byte & Self::RESERVED_MASK != 0 {
+            return Err(DecodingError::InvalidVersion(byte));
+        }
+        Ok(VersionByte(byte))
+    }
+
+    /// The raw version byte.
+    pub fn to_byte(self) -> u8 {
+        self.0
+    }
+
+    /// The address type (0 to 15).
+    pub fn address_type(self) -> u8 {
+        (self.0 & Self::TYPE_MASK) >> 3
+    }
+
+    /// The size bits (0 to 7).
+    pub fn size(self) -> u8 {
+        self.0 & Self::SIZE_MASK
+    }
+
+    /// The length of the hash in bytes.
+    pub fn hash_len(self) -> usize {
+        HASH_LENGTHS[self.size() as usize]
+    }
+
+    /// The hash type, if the address type is known.
+    pub fn hash_type(self) -> Option<HashType> {
+        match self.address_type() {
+            Self::TYPE_P2PKH => Some(HashType::Key),
+            Self::TYPE_P2SH => Some(HashType::Script),
+            Self::TYPE_TOKEN_P2PKH => Some(HashType::TokenKey),
+            Self::TYPE_TOKEN_P2SH => Some(HashType::TokenScript),
+            _ => None,
+        }
+    }
+
+    /// The hash type, falling back to `HashType::Unknown` for unrecognised address types.
+    pub fn hash_type_lenient(self) -> HashType {
+        self.hash_type()
+            .unwrap_or(HashType::Unknown(self.address_type()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        for byte in 0..0x80 {
+            let version = VersionByte::from_byte(byte).unwrap();
+            assert_eq!(version.to_byte(), byte);
+            assert_eq!(
+                VersionByte::new(version.address_type(), version.hash_len()),
+                Some(version)
+            );
+        }
+        for byte in 0x80..=0xff {
+            assert_eq!(
+                VersionByte::from_byte(byte),
+                Err(DecodingError::InvalidVersion(byte))
+            );
+        }
+    }
+
+    #[test]
+    fn fields() {
+        let version = VersionByte::from_hash_type(&HashType::TokenScript, 32).unwrap();
+        assert_eq!(version.to_byte(), 0x1b);
+        assert_eq!(version.address_type(), VersionByte::TYPE_TOKEN_P2SH);
+        assert_eq!(version.size(), 3);
+        assert_eq!(version.hash_type(), Some(HashType::TokenScript));
+
+        assert_eq!(VersionByte::new(16, 20), None);
+        assert_eq!(VersionByte::new(0, 21), None);
+        assert_eq!(VersionByte::new(15, 64).unwrap().hash_type(), None);
+        assert_eq!(
+            VersionByte::from_hash_type(&HashType::Key, 33),
+            Err(EncodingError::InvalidLength(33))
+        );
+
+        let unknown = VersionByte::from_hash_type(&HashType::Unknown(7), 20).unwrap();
+        assert_eq!(unknown.to_byte(), 0x38);
+        assert_eq!(unknown.hash_type(), None);
+        assert_eq!(unknown.hash_type_lenient(), HashType::Unknown(7));
+        for address_type in &[0, 3, 16] {
+            assert_eq!(
+                VersionByte::from_hash_type(&HashType::Unknown(*address_type), 20),
+                Err(EncodingError::InvalidType(*address_type))
+            );
+        }
+    }
+}
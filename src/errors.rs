@@ -0,0 +1,74 @@
+use core::fmt;
+#[cfg(feature = "std")]
+use std::error::Error;
+
+use crate::{base58, cashaddr};
+
+/// Error concerning encoding of addresses in either scheme.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum EncodingError {
+    /// CashAddr encoding failed.
+    CashAddr(cashaddr::EncodingError),
+    /// Base58 encoding failed.
+    Base58(base58::EncodingError),
+}
+
+impl fmt::Display for EncodingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EncodingError::CashAddr(err) => write!(f, "cashaddr encoding failed: {}", err),
+            EncodingError::Base58(err) => write!(f, "base58 encoding failed: {}", err),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Error for EncodingError {
+    fn cause(&self) -> Option<&dyn Error> {
+        match self {
+            EncodingError::CashAddr(err) => Some(err),
+            EncodingError::Base58(err) => Some(err),
+        }
+    }
+    fn description(&self) -> &str {
+        match *self {
+            EncodingError::CashAddr(_) => "cashaddr encoding failed",
+            EncodingError::Base58(_) => "base58 encoding failed",
+        }
+    }
+}
+
+/// Error concerning the SEC encoding of a public key.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum PublicKeyError {
+    /// Neither a 33-byte compressed nor a 65-byte uncompressed key (length).
+    InvalidLength(usize),
+    /// Prefix byte does not match the key length (prefix).
+    InvalidPrefix(u8),
+    /// Key is not a point on the secp256k1 curve.
+    NotOnCurve,
+}
+
+impl fmt::Display for PublicKeyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PublicKeyError::InvalidLength(length) => write!(f, "invalid length ({})", length),
+            PublicKeyError::InvalidPrefix(prefix) => write!(f, "invalid prefix ({})", prefix),
+            PublicKeyError::NotOnCurve => write!(f, "not on curve"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Error for PublicKeyError {
+    fn cause(&self) -> Option<&dyn Error> {
+        None
+    }
+    fn description(&self) -> &str {
+        match *self {
+            PublicKeyError::InvalidLength(_) => "invalid length",
+            PublicKeyError::InvalidPrefix(_) => "invalid prefix",
+            PublicKeyError::NotOnCurve => "not on curve",
+        }
+    }
+}
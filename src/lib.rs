@@ -24,12 +24,38 @@
 //!
 //! ```
 //!
+//! The `std` feature is enabled by default. Disabling it builds the crate with `#![no_std]`,
+//! requiring only the `alloc` crate.
+//!
+//! The optional `secp256k1` feature enables BIP32 public derivation of addresses from
+//! extended keys and computing the addresses of WIF private keys, and checks that public
+//! keys are points on the curve.
+//!
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
 
 pub mod base58;
+pub mod bip32;
 pub mod cashaddr;
+pub mod errors;
+pub mod script;
+pub mod transaction;
+pub mod wif;
+
+use alloc::{borrow::ToOwned, string::String, vec, vec::Vec};
+use core::fmt;
+
+use bitcoin_hashes::{hash160::Hash as Hash160, sha256d::Hash as Sha256d, Hash};
 
 pub use base58::Base58Codec;
+pub use bip32::ExtendedKey;
 pub use cashaddr::CashAddrCodec;
+pub use errors::{EncodingError, PublicKeyError};
+pub use script::ScriptKind;
+pub use transaction::Transaction;
+pub use wif::Wif;
 
 /// Bitcoin Networks.
 #[derive(PartialEq, Eq, Clone, Debug, Hash)]
@@ -40,6 +66,10 @@ pub enum Network {
     Test,
     /// Regression test network.
     Regtest,
+    /// Any other network, identified by its CashAddr prefix (prefix).
+    ///
+    /// The prefixes of the built-in networks are rejected when encoding.
+    Other(String),
 }
 
 /// Address encoding scheme.
@@ -58,6 +88,41 @@ pub enum HashType {
     Key,
     /// Script hash
     Script,
+    /// Token-aware public key hash
+    TokenKey,
+    /// Token-aware script hash
+    TokenScript,
+    /// Unrecognised CashAddr address type, only produced by lenient decoding (type).
+    Unknown(u8),
+}
+
+impl HashType {
+    /// Whether the hash type signals that the address can receive CashTokens.
+    pub fn is_token_aware(&self) -> bool {
+        match self {
+            HashType::TokenKey | HashType::TokenScript => true,
+            HashType::Key | HashType::Script | HashType::Unknown(_) => false,
+        }
+    }
+
+    /// Convert to the token-aware form of the hash type. Unknown types are unchanged.
+    pub fn into_token_aware(self) -> Self {
+        match self {
+            HashType::Key | HashType::TokenKey => HashType::TokenKey,
+            HashType::Script | HashType::TokenScript => HashType::TokenScript,
+            HashType::Unknown(address_type) => HashType::Unknown(address_type),
+        }
+    }
+
+    /// Convert to the plain (non-token-aware) form of the hash type. Unknown types are
+    /// unchanged.
+    pub fn into_plain(self) -> Self {
+        match self {
+            HashType::Key | HashType::TokenKey => HashType::Key,
+            HashType::Script | HashType::TokenScript => HashType::Script,
+            HashType::Unknown(address_type) => HashType::Unknown(address_type),
+        }
+    }
 }
 
 /// Struct containing the bytes and metadata of a Bitcoin Cash address.
@@ -98,6 +163,68 @@ impl Address {
         }
     }
 
+    /// Create a P2PKH address from a compressed (33-byte) or uncompressed (65-byte) SEC
+    /// encoded public key, hashed using Hash160.
+    ///
+    /// With the `secp256k1` feature the key must be a point on the curve. Without it, only
+    /// the length and prefix byte are checked.
+    pub fn from_pubkey(pubkey: &[u8], network: Network) -> Result<Self, PublicKeyError> {
+        check_pubkey(pubkey)?;
+        Ok(Address {
+            body: Hash160::hash(pubkey).to_vec(),
+            scheme: Scheme::CashAddr,
+            hash_type: HashType::Key,
+            network,
+        })
+    }
+
+    /// Create a P2SH address from a redeem script, hashed using Hash160.
+    pub fn from_redeem_script(script: &[u8], network: Network) -> Self {
+        Address {
+            body: Hash160::hash(script).to_vec(),
+            scheme: Scheme::CashAddr,
+            hash_type: HashType::Script,
+            network,
+        }
+    }
+
+    /// Create a P2SH32 address from a redeem script, hashed using double SHA-256.
+    pub fn from_redeem_script_p2sh32(script: &[u8], network: Network) -> Self {
+        Address {
+            body: Sha256d::hash(script).to_vec(),
+            scheme: Scheme::CashAddr,
+            hash_type: HashType::Script,
+            network,
+        }
+    }
+
+    /// Whether the address pays to a 20-byte script hash.
+    pub fn is_p2sh20(&self) -> bool {
+        self.is_script_hash() && self.body.len() == 20
+    }
+
+    /// Whether the address pays to a 32-byte script hash.
+    pub fn is_p2sh32(&self) -> bool {
+        self.is_script_hash() && self.body.len() == 32
+    }
+
+    /// Whether the address corresponds to a standard locking script: a 20-byte public key
+    /// hash, or a 20 or 32-byte script hash, either of which may be token-aware.
+    pub fn is_standard(&self) -> bool {
+        match self.hash_type {
+            HashType::Key | HashType::TokenKey => self.body.len() == 20,
+            HashType::Script | HashType::TokenScript => self.is_p2sh20() || self.is_p2sh32(),
+            HashType::Unknown(_) => false,
+        }
+    }
+
+    fn is_script_hash(&self) -> bool {
+        match self.hash_type {
+            HashType::Script | HashType::TokenScript => true,
+            HashType::Key | HashType::TokenKey | HashType::Unknown(_) => false,
+        }
+    }
+
     /// Borrow address bytes.
     pub fn as_body(&self) -> &[u8] {
         &self.body
@@ -108,20 +235,37 @@ impl Address {
         self.body
     }
 
+    /// Convert to the token-aware form of the address, keeping the same hash.
+    pub fn into_token_aware(self) -> Self {
+        Address {
+            hash_type: self.hash_type.into_token_aware(),
+            ..self
+        }
+    }
+
+    /// Convert to the plain (non-token-aware) form of the address, keeping the same hash.
+    pub fn into_plain(self) -> Self {
+        Address {
+            hash_type: self.hash_type.into_plain(),
+            ..self
+        }
+    }
+
     /// Attempt to convert the raw address bytes to a string.
-    pub fn encode(&self) -> Result<String, cashaddr::EncodingError> {
+    pub fn encode(&self) -> Result<String, EncodingError> {
         match self.scheme {
             Scheme::CashAddr => CashAddrCodec::encode(
                 &self.body,
                 self.hash_type.to_owned(),
                 self.network.to_owned(),
-            ),
-            Scheme::Base58 => Ok(Base58Codec::encode(
+            )
+            .map_err(EncodingError::CashAddr),
+            Scheme::Base58 => Base58Codec::encode(
                 &self.body,
                 self.hash_type.to_owned(),
                 self.network.to_owned(),
             )
-            .unwrap()), // Base58 encoding is infallible
+            .map_err(EncodingError::Base58),
         }
     }
 
@@ -135,6 +279,78 @@ impl Address {
     }
 }
 
+/// Writes the encoded address, without allocating. Addresses which cannot be encoded in
+/// their scheme are written in their `Debug` form instead.
+impl fmt::Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // Only failures of the writer are errors
+        let encoded = match self.scheme {
+            Scheme::CashAddr => {
+                match CashAddrCodec::encode_to_fmt(&self.body, &self.hash_type, &self.network, f) {
+                    Err(cashaddr::EncodingError::Fmt(err)) => return Err(err),
+                    result => result.is_ok(),
+                }
+            }
+            Scheme::Base58 => {
+                match Base58Codec::encode_to_fmt(&self.body, &self.hash_type, &self.network, f) {
+                    Err(base58::EncodingError::Fmt(err)) => return Err(err),
+                    result => result.is_ok(),
+                }
+            }
+        };
+        if encoded {
+            Ok(())
+        } else {
+            fmt::Debug::fmt(self, f)
+        }
+    }
+}
+
+// Compare secret bytes in time independent of their contents.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    let diff = a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y));
+    a.len() == b.len() && core::hint::black_box(diff) == 0
+}
+
+// Order of the secp256k1 group, big endian.
+const CURVE_ORDER: [u8; 32] = [
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xfe,
+    0xba, 0xae, 0xdc, 0xe6, 0xaf, 0x48, 0xa0, 0x3b, 0xbf, 0xd2, 0x5e, 0x8c, 0xd0, 0x36, 0x41, 0x41,
+];
+
+// Check a 32-byte secret key is in the range [1, n), in time independent of its value.
+fn is_valid_secret(secret: &[u8]) -> bool {
+    if secret.len() != CURVE_ORDER.len() {
+        return false;
+    }
+    // The secret is below n if subtracting n borrows
+    let mut borrow = 0u16;
+    let mut nonzero = 0u8;
+    for (byte, order) in secret.iter().zip(&CURVE_ORDER).rev() {
+        let diff = u16::from(*byte)
+            .wrapping_sub(u16::from(*order))
+            .wrapping_sub(borrow);
+        borrow = diff >> 15;
+        nonzero |= byte;
+    }
+    core::hint::black_box(borrow) == 1 && core::hint::black_box(nonzero) != 0
+}
+
+// Check the length and prefix byte of a SEC encoded public key, and with the secp256k1
+// feature that it is a point on the curve.
+fn check_pubkey(pubkey: &[u8]) -> Result<(), PublicKeyError> {
+    match (pubkey.len(), pubkey.first()) {
+        (33, Some(0x02)) | (33, Some(0x03)) | (65, Some(0x04)) => {}
+        (33, Some(prefix)) | (65, Some(prefix)) => {
+            return Err(PublicKeyError::InvalidPrefix(*prefix))
+        }
+        (length, _) => return Err(PublicKeyError::InvalidLength(length)),
+    }
+    #[cfg(feature = "secp256k1")]
+    secp256k1::PublicKey::from_slice(pubkey).map_err(|_| PublicKeyError::NotOnCurve)?;
+    Ok(())
+}
+
 /// A trait providing an interface for encoding and decoding the `Address` struct for each address scheme.
 pub trait AddressCodec {
     type EncodingError;
@@ -150,3 +366,169 @@ pub trait AddressCodec {
     /// Attempt to convert the address string to bytes.
     fn decode(s: &str) -> Result<Address, Self::DecodingError>;
 }
+
+// Adapter writing formatted output into a fixed buffer, failing once it is full.
+struct SliceWriter<'a> {
+    buf: &'a mut [u8],
+    len: usize,
+}
+
+impl<'a> SliceWriter<'a> {
+    fn new(buf: &'a mut [u8]) -> Self {
+        SliceWriter { buf, len: 0 }
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+}
+
+impl fmt::Write for SliceWriter<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let end = self.len + s.len();
+        if end > self.buf.len() {
+            return Err(fmt::Error);
+        }
+        self.buf[self.len..end].copy_from_slice(s.as_bytes());
+        self.len = end;
+        Ok(())
+    }
+}
+
+// Every string of up to `max_len` characters drawn from `alphabet`, shortest first.
+#[cfg(test)]
+fn short_strings(alphabet: &[char], max_len: usize) -> Vec<String> {
+    let mut strings = vec![String::new()];
+    let mut start = 0;
+    for _ in 0..max_len {
+        let end = strings.len();
+        for i in start..end {
+            for c in alphabet {
+                let mut string = strings[i].clone();
+                string.push(*c);
+                strings.push(string);
+            }
+        }
+        start = end;
+    }
+    strings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::{format, string::ToString};
+
+    #[test]
+    fn secret_range() {
+        let mut secret = [0u8; 32];
+        assert!(!is_valid_secret(&secret));
+        secret[31] = 1;
+        assert!(is_valid_secret(&secret));
+
+        let mut below_order = CURVE_ORDER;
+        below_order[31] -= 1;
+        assert!(is_valid_secret(&below_order));
+        assert!(!is_valid_secret(&CURVE_ORDER));
+        let mut above_order = CURVE_ORDER;
+        above_order[15] = 0xff;
+        assert!(!is_valid_secret(&above_order));
+        assert!(!is_valid_secret(&[0xff; 32]));
+    }
+
+    #[test]
+    fn display() {
+        let data = hex::decode("F5BF48B397DAE70BE82B3CCA4793F8EB2B6CDAC9").unwrap();
+        let mut address = Address::new(data, Scheme::CashAddr, HashType::Key, Network::Main);
+        assert_eq!(address.to_string(), address.encode().unwrap());
+        address.scheme = Scheme::Base58;
+        assert_eq!(address.to_string(), address.encode().unwrap());
+
+        // Unencodable addresses fall back to their debug form
+        let unencodable = [
+            Address::new(
+                vec![0; 20],
+                Scheme::Base58,
+                HashType::TokenKey,
+                Network::Main,
+            ),
+            Address::new(vec![0; 32], Scheme::Base58, HashType::Script, Network::Main),
+            Address::new(
+                vec![0; 20],
+                Scheme::CashAddr,
+                HashType::Key,
+                Network::Other("e-cash".to_string()),
+            ),
+        ];
+        for address in &unencodable {
+            assert!(address.encode().is_err());
+            assert_eq!(address.to_string(), format!("{:?}", address));
+        }
+    }
+
+    #[test]
+    fn from_pubkey() {
+        let compressed =
+            hex::decode("0339a36013301597daef41fbe593a02cc513d0b55527ec2df1050e2e8ff49c85c2")
+                .unwrap();
+        let address = Address::from_pubkey(&compressed, Network::Main).unwrap();
+        assert_eq!(
+            hex::encode(address.as_body()),
+            "3442193e1bb70916e914552172cd4e2dbc9df811"
+        );
+        assert_eq!(address.hash_type, HashType::Key);
+
+        let uncompressed = hex::decode("04005937fd439b3c19014d5f328df8c7ed514eaaf41c1980b8aeab461dffb23fbf3317e42395db24a52ce9fc947d9c22f54dc3217c8b11dfc7a09c59e0dca591d3").unwrap();
+        let mut address = Address::from_pubkey(&uncompressed, Network::Main).unwrap();
+        address.scheme = Scheme::Base58;
+        assert_eq!(
+            address.encode().unwrap(),
+            "1NM2HFXin4cEQRBLjkNZAS98qLX9JKzjKn"
+        );
+
+        assert_eq!(
+            Address::from_pubkey(&compressed[1..], Network::Main),
+            Err(PublicKeyError::InvalidLength(32))
+        );
+        assert_eq!(
+            Address::from_pubkey(&[], Network::Main),
+            Err(PublicKeyError::InvalidLength(0))
+        );
+        assert_eq!(
+            Address::from_pubkey(&uncompressed[..33], Network::Main),
+            Err(PublicKeyError::InvalidPrefix(0x04))
+        );
+
+        // x = 5 is not the x-coordinate of a curve point
+        let mut off_curve = [0u8; 33];
+        off_curve[0] = 0x02;
+        off_curve[32] = 5;
+        let result = Address::from_pubkey(&off_curve, Network::Main);
+        if cfg!(feature = "secp256k1") {
+            assert_eq!(result, Err(PublicKeyError::NotOnCurve));
+        } else {
+            assert!(result.is_ok());
+        }
+        let mut hybrid = uncompressed.clone();
+        hybrid[0] = 0x06;
+        assert_eq!(
+            Address::from_pubkey(&hybrid, Network::Main),
+            Err(PublicKeyError::InvalidPrefix(0x06))
+        );
+    }
+
+    #[test]
+    fn from_redeem_script() {
+        let mut address = Address::from_redeem_script(&[0x51], Network::Main);
+        assert!(address.is_p2sh20());
+        assert_eq!(
+            hex::encode(address.as_body()),
+            "da1745e9b549bd0bfa1a569971c77eba30cd5a4b"
+        );
+        address.scheme = Scheme::Base58;
+        assert_eq!(
+            address.encode().unwrap(),
+            "3MaB7QVq3k4pQx3BhsvEADgzQonLSBwMdj"
+        );
+    }
+}
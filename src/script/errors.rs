@@ -0,0 +1,121 @@
+use core::fmt;
+#[cfg(feature = "std")]
+use std::error::Error;
+
+use crate::{HashType, PublicKeyError};
+
+/// Error concerning conversion of addresses to locking scripts.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum LockingScriptError {
+    /// Hash type and length do not correspond to a standard locking script.
+    NonStandard { hash_type: HashType, length: usize },
+}
+
+impl fmt::Display for LockingScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LockingScriptError::NonStandard { hash_type, length } => write!(
+                f,
+                "non-standard address ({:?} with length {})",
+                hash_type, length
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Error for LockingScriptError {
+    fn cause(&self) -> Option<&dyn Error> {
+        None
+    }
+    fn description(&self) -> &str {
+        match *self {
+            LockingScriptError::NonStandard { .. } => "non-standard address",
+        }
+    }
+}
+
+/// Reason a locking script has no address.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum NoAddress {
+    /// Unspendable OP_RETURN output.
+    NullData,
+    /// Bare multisig output.
+    Multisig,
+    /// Pay to public key output.
+    PublicKey,
+    /// Script does not match a standard template.
+    NonStandard,
+    /// CashTokens prefix is malformed.
+    InvalidTokenPrefix,
+}
+
+impl fmt::Display for NoAddress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NoAddress::NullData => write!(f, "null data output"),
+            NoAddress::Multisig => write!(f, "bare multisig output"),
+            NoAddress::PublicKey => write!(f, "pay to public key output"),
+            NoAddress::NonStandard => write!(f, "non-standard script"),
+            NoAddress::InvalidTokenPrefix => write!(f, "invalid token prefix"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Error for NoAddress {
+    fn cause(&self) -> Option<&dyn Error> {
+        None
+    }
+    fn description(&self) -> &str {
+        match *self {
+            NoAddress::NullData => "null data output",
+            NoAddress::Multisig => "bare multisig output",
+            NoAddress::PublicKey => "pay to public key output",
+            NoAddress::NonStandard => "non-standard script",
+            NoAddress::InvalidTokenPrefix => "invalid token prefix",
+        }
+    }
+}
+
+/// Error concerning construction of redeem scripts from templates.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum TemplateError {
+    /// Public key is malformed.
+    PublicKey(PublicKeyError),
+    /// Relative lock time has the disable flag set, so would not be enforced (sequence).
+    DisabledSequence(u32),
+}
+
+impl From<PublicKeyError> for TemplateError {
+    fn from(err: PublicKeyError) -> Self {
+        TemplateError::PublicKey(err)
+    }
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TemplateError::PublicKey(err) => write!(f, "invalid public key: {}", err),
+            TemplateError::DisabledSequence(sequence) => {
+                write!(f, "relative lock time disabled ({:#010x})", sequence)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Error for TemplateError {
+    fn cause(&self) -> Option<&dyn Error> {
+        match self {
+            TemplateError::PublicKey(err) => Some(err),
+            _ => None,
+        }
+    }
+    fn description(&self) -> &str {
+        match *self {
+            TemplateError::PublicKey(_) => "invalid public key",
+            TemplateError::DisabledSequence(_) => "relative lock time disabled",
+        }
+    }
+}
@@ -0,0 +1,538 @@
+pub mod errors;
+mod templates;
+
+use alloc::{vec, vec::Vec};
+
+use bitcoin_hashes::{hash160::Hash as Hash160, Hash};
+
+use crate::{check_pubkey, Address, HashType, Network, Scheme};
+pub use errors::{LockingScriptError, NoAddress, TemplateError};
+pub use templates::{Contract, P2shType};
+
+// Opcodes
+const OP_0: u8 = 0x00;
+const OP_PUSHDATA1: u8 = 0x4c;
+const OP_PUSHDATA2: u8 = 0x4d;
+const OP_PUSHDATA4: u8 = 0x4e;
+const OP_1NEGATE: u8 = 0x4f;
+const OP_1: u8 = 0x51;
+const OP_16: u8 = 0x60;
+const OP_RETURN: u8 = 0x6a;
+const OP_DUP: u8 = 0x76;
+const OP_EQUAL: u8 = 0x87;
+const OP_EQUALVERIFY: u8 = 0x88;
+const OP_HASH160: u8 = 0xa9;
+const OP_HASH256: u8 = 0xaa;
+const OP_CHECKSIG: u8 = 0xac;
+const OP_CHECKMULTISIG: u8 = 0xae;
+
+// CashTokens prefix and token bitfield flags
+const PREFIX_TOKEN: u8 = 0xef;
+const TOKEN_RESERVED: u8 = 0x80;
+const TOKEN_HAS_COMMITMENT: u8 = 0x40;
+const TOKEN_HAS_NFT: u8 = 0x20;
+const TOKEN_HAS_AMOUNT: u8 = 0x10;
+const TOKEN_CAPABILITY_MASK: u8 = 0x0f;
+const TOKEN_MAX_CAPABILITY: u8 = 0x02;
+
+// Length of a token category ID
+const TOKEN_CATEGORY_LEN: usize = 32;
+
+// A single script instruction.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub(crate) enum Instruction<'a> {
+    // Data push, including OP_0
+    Push(&'a [u8]),
+    // Any other opcode
+    Op(u8),
+}
+
+// Read a little endian integer of `len` bytes, advancing `data`.
+pub(crate) fn read_le(data: &mut &[u8], len: usize) -> Option<u64> {
+    if data.len() < len {
+        return None;
+    }
+    let (bytes, rest) = data.split_at(len);
+    *data = rest;
+    Some(
+        bytes
+            .iter()
+            .rev()
+            .fold(0, |acc, byte| (acc << 8) | u64::from(*byte)),
+    )
+}
+
+// Read a minimally encoded CompactSize integer, advancing `data`.
+pub(crate) fn read_compact_size(data: &mut &[u8]) -> Option<u64> {
+    let (value, min) = match read_le(data, 1)? {
+        0xfd => (read_le(data, 2)?, 0xfd),
+        0xfe => (read_le(data, 4)?, 0x1_0000),
+        0xff => (read_le(data, 8)?, 0x1_0000_0000),
+        value => (value, 0),
+    };
+    if value < min {
+        return None;
+    }
+    Some(value)
+}
+
+// Split a script into instructions, failing if a push runs past the end.
+pub(crate) fn instructions(mut script: &[u8]) -> Option<Vec<Instruction<'_>>> {
+    let mut ret = Vec::new();
+    while let Some((&opcode, rest)) = script.split_first() {
+        script = rest;
+        let len = match opcode {
+            OP_0 => 0,
+            0x01..=0x4b => u64::from(opcode),
+            OP_PUSHDATA1 => read_le(&mut script, 1)?,
+            OP_PUSHDATA2 => read_le(&mut script, 2)?,
+            OP_PUSHDATA4 => read_le(&mut script, 4)?,
+            _ => {
+                ret.push(Instruction::Op(opcode));
+                continue;
+            }
+        };
+        if len > script.len() as u64 {
+            return None;
+        }
+        let (data, rest) = script.split_at(len as usize);
+        script = rest;
+        ret.push(Instruction::Push(data));
+    }
+    Some(ret)
+}
+
+// Get the value of OP_1 to OP_16.
+fn small_int(opcode: u8) -> Option<u8> {
+    match opcode {
+        OP_1..=OP_16 => Some(opcode - OP_1 + 1),
+        _ => None,
+    }
+}
+
+// Split off the CashTokens prefix, if any, returning whether one was present and the
+// remaining locking bytecode.
+fn strip_token_prefix(script: &[u8]) -> Result<(bool, &[u8]), NoAddress> {
+    let mut data = match script.split_first() {
+        Some((&PREFIX_TOKEN, rest)) => rest,
+        _ => return Ok((false, script)),
+    };
+    if data.len() < TOKEN_CATEGORY_LEN + 1 {
+        return Err(NoAddress::InvalidTokenPrefix);
+    }
+    let bitfield = data[TOKEN_CATEGORY_LEN];
+    data = &data[TOKEN_CATEGORY_LEN + 1..];
+
+    // Check the flags are consistent
+    let capability = bitfield & TOKEN_CAPABILITY_MASK;
+    let has_nft = bitfield & TOKEN_HAS_NFT != 0;
+    let has_commitment = bitfield & TOKEN_HAS_COMMITMENT != 0;
+    let has_amount = bitfield & TOKEN_HAS_AMOUNT != 0;
+    if bitfield & TOKEN_RESERVED != 0
+        || capability > TOKEN_MAX_CAPABILITY
+        || !(has_nft || has_amount)
+        || (!has_nft && (capability != 0 || has_commitment))
+    {
+        return Err(NoAddress::InvalidTokenPrefix);
+    }
+
+    // Skip the non-empty commitment and the amount, which must be positive
+    if has_commitment {
+        match read_compact_size(&mut data) {
+            Some(len) if len > 0 && len <= data.len() as u64 => data = &data[len as usize..],
+            _ => return Err(NoAddress::InvalidTokenPrefix),
+        }
+    }
+    if has_amount {
+        match read_compact_size(&mut data) {
+            Some(amount) if amount > 0 && amount <= i64::MAX as u64 => {}
+            _ => return Err(NoAddress::InvalidTokenPrefix),
+        }
+    }
+    Ok((true, data))
+}
+
+/// Classification of a locking script.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum ScriptKind {
+    /// Pay to public key hash (address).
+    P2pkh(Address),
+    /// Pay to 20-byte script hash (address).
+    P2sh20(Address),
+    /// Pay to 32-byte script hash (address).
+    P2sh32(Address),
+    /// Pay to public key (public key, address of the public key hash).
+    P2pk { pubkey: Vec<u8>, address: Address },
+    /// Bare multisig (required signatures, public keys).
+    Multisig { required: u8, pubkeys: Vec<Vec<u8>> },
+    /// Unspendable OP_RETURN output (pushed stack items).
+    NullData(Vec<Vec<u8>>),
+    /// Script does not match a standard template, or has a malformed CashTokens prefix.
+    NonStandard,
+}
+
+impl ScriptKind {
+    /// Classify a locking script, with any addresses on the given network.
+    ///
+    /// A CashTokens prefix is stripped, and the addresses of prefixed scripts are
+    /// token-aware.
+    pub fn from_locking_script(script: &[u8], network: Network) -> Self {
+        match strip_token_prefix(script) {
+            Ok((token_aware, bytecode)) => classify(bytecode, token_aware, network),
+            Err(_) => ScriptKind::NonStandard,
+        }
+    }
+
+    /// The address paid to, or implied by the public key of a P2PK script.
+    pub fn address(&self) -> Option<&Address> {
+        match self {
+            ScriptKind::P2pkh(address)
+            | ScriptKind::P2sh20(address)
+            | ScriptKind::P2sh32(address)
+            | ScriptKind::P2pk { address, .. } => Some(address),
+            ScriptKind::Multisig { .. } | ScriptKind::NullData(_) | ScriptKind::NonStandard => None,
+        }
+    }
+}
+
+// Classify the locking bytecode following any token prefix.
+fn classify(bytecode: &[u8], token_aware: bool, network: Network) -> ScriptKind {
+    let address = |body: &[u8], hash_type: HashType| Address {
+        body: body.to_vec(),
+        scheme: Scheme::CashAddr,
+        hash_type: if token_aware {
+            hash_type.into_token_aware()
+        } else {
+            hash_type
+        },
+        network: network.clone(),
+    };
+    match bytecode {
+        [OP_DUP, OP_HASH160, 20, hash @ .., OP_EQUALVERIFY, OP_CHECKSIG] if hash.len() == 20 => {
+            return ScriptKind::P2pkh(address(hash, HashType::Key))
+        }
+        [OP_HASH160, 20, hash @ .., OP_EQUAL] if hash.len() == 20 => {
+            return ScriptKind::P2sh20(address(hash, HashType::Script))
+        }
+        [OP_HASH256, 32, hash @ .., OP_EQUAL] if hash.len() == 32 => {
+            return ScriptKind::P2sh32(address(hash, HashType::Script))
+        }
+        _ => {}
+    }
+
+    let instructions = match instructions(bytecode) {
+        Some(instructions) => instructions,
+        None => return ScriptKind::NonStandard,
+    };
+    match instructions.as_slice() {
+        // OP_RETURN followed only by pushes
+        [Instruction::Op(OP_RETURN), data @ ..] => data
+            .iter()
+            .map(|instruction| match instruction {
+                Instruction::Push(data) => Some(data.to_vec()),
+                Instruction::Op(OP_1NEGATE) => Some(vec![0x81]),
+                Instruction::Op(opcode) => small_int(*opcode).map(|n| vec![n]),
+            })
+            .collect::<Option<_>>()
+            .map_or(ScriptKind::NonStandard, ScriptKind::NullData),
+        // <pubkey> OP_CHECKSIG
+        [Instruction::Push(key), Instruction::Op(OP_CHECKSIG)] if check_pubkey(key).is_ok() => {
+            ScriptKind::P2pk {
+                pubkey: key.to_vec(),
+                address: address(&Hash160::hash(key)[..], HashType::Key),
+            }
+        }
+        // OP_m <pubkeys> OP_n OP_CHECKMULTISIG
+        [Instruction::Op(m), keys @ .., Instruction::Op(n), Instruction::Op(OP_CHECKMULTISIG)] => {
+            let pubkeys: Option<Vec<Vec<u8>>> = keys
+                .iter()
+                .map(|key| match key {
+                    Instruction::Push(key) if check_pubkey(key).is_ok() => Some(key.to_vec()),
+                    _ => None,
+                })
+                .collect();
+            match (small_int(*m), small_int(*n), pubkeys) {
+                (Some(required), Some(n), Some(pubkeys))
+                    if required <= n && pubkeys.len() == n as usize =>
+                {
+                    ScriptKind::Multisig { required, pubkeys }
+                }
+                _ => ScriptKind::NonStandard,
+            }
+        }
+        _ => ScriptKind::NonStandard,
+    }
+}
+
+impl Address {
+    /// Attempt to recognise the address paid to by a locking script, as a cashaddr on the
+    /// given network.
+    ///
+    /// P2PKH, P2SH20 and P2SH32 scripts are recognised. If the script carries a CashTokens
+    /// prefix, it is stripped and the token-aware form of the address is returned. Otherwise
+    /// the reason the script has no address is returned.
+    pub fn from_locking_script(script: &[u8], network: Network) -> Result<Self, NoAddress> {
+        let (token_aware, bytecode) = strip_token_prefix(script)?;
+        match classify(bytecode, token_aware, network) {
+            ScriptKind::P2pkh(address)
+            | ScriptKind::P2sh20(address)
+            | ScriptKind::P2sh32(address) => Ok(address),
+            ScriptKind::P2pk { .. } => Err(NoAddress::PublicKey),
+            ScriptKind::Multisig { .. } => Err(NoAddress::Multisig),
+            ScriptKind::NullData(_) => Err(NoAddress::NullData),
+            ScriptKind::NonStandard => Err(NoAddress::NonStandard),
+        }
+    }
+
+    /// Attempt to build the locking script paying to the address.
+    ///
+    /// Token-aware addresses share the locking script of their plain form. Hash types and
+    /// lengths other than those accepted by `is_standard` have no locking script.
+    pub fn to_locking_script(&self) -> Result<Vec<u8>, LockingScriptError> {
+        let body = &self.body;
+        let script = match (&self.hash_type, body.len()) {
+            // OP_DUP OP_HASH160 <20> OP_EQUALVERIFY OP_CHECKSIG
+            (HashType::Key, 20) | (HashType::TokenKey, 20) => {
+                let mut script = Vec::with_capacity(25);
+                script.extend_from_slice(&[OP_DUP, OP_HASH160, 20]);
+                script.extend_from_slice(body);
+                script.extend_from_slice(&[OP_EQUALVERIFY, OP_CHECKSIG]);
+                script
+            }
+            // OP_HASH160 <20> OP_EQUAL
+            (HashType::Script, 20) | (HashType::TokenScript, 20) => {
+                let mut script = Vec::with_capacity(23);
+                script.extend_from_slice(&[OP_HASH160, 20]);
+                script.extend_from_slice(body);
+                script.push(OP_EQUAL);
+                script
+            }
+            // OP_HASH256 <32> OP_EQUAL
+            (HashType::Script, 32) | (HashType::TokenScript, 32) => {
+                let mut script = Vec::with_capacity(35);
+                script.extend_from_slice(&[OP_HASH256, 32]);
+                script.extend_from_slice(body);
+                script.push(OP_EQUAL);
+                script
+            }
+            (hash_type, length) => {
+                return Err(LockingScriptError::NonStandard {
+                    hash_type: hash_type.clone(),
+                    length,
+                })
+            }
+        };
+        Ok(script)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HASH160: &str = "ea2407829a5055466b27784cde8cf463167946bf";
+
+    #[test]
+    fn to_locking_script() {
+        let body = hex::decode(HASH160).unwrap();
+        let key = Address::new(body.clone(), Scheme::CashAddr, HashType::Key, Network::Main);
+        let expected = ["76a914", HASH160, "88ac"].concat();
+        assert_eq!(hex::encode(key.to_locking_script().unwrap()), expected);
+        let token_key = key.into_token_aware();
+        assert_eq!(
+            hex::encode(token_key.to_locking_script().unwrap()),
+            expected
+        );
+
+        let script = Address::new(body, Scheme::Base58, HashType::Script, Network::Test);
+        let expected = ["a914", HASH160, "87"].concat();
+        assert_eq!(hex::encode(script.to_locking_script().unwrap()), expected);
+
+        let p2sh32 = Address::from_redeem_script_p2sh32(&[0x51], Network::Main);
+        let expected = ["aa20", &hex::encode(p2sh32.as_body()), "87"].concat();
+        assert_eq!(hex::encode(p2sh32.to_locking_script().unwrap()), expected);
+    }
+
+    #[test]
+    fn non_standard() {
+        let cases = [
+            (HashType::Key, 32),
+            (HashType::TokenScript, 24),
+            (HashType::Unknown(7), 20),
+            (HashType::Script, 0),
+        ];
+        for (hash_type, length) in cases.iter() {
+            let address = Address::new(
+                vec![0; *length],
+                Scheme::CashAddr,
+                hash_type.clone(),
+                Network::Main,
+            );
+            assert!(!address.is_standard());
+            assert_eq!(
+                address.to_locking_script(),
+                Err(LockingScriptError::NonStandard {
+                    hash_type: hash_type.clone(),
+                    length: *length,
+                })
+            );
+        }
+    }
+
+    // Token prefix of a fungible amount and an NFT with a commitment
+    fn token_prefix() -> Vec<u8> {
+        let mut prefix = vec![PREFIX_TOKEN];
+        prefix.extend_from_slice(&[0xaa; 32]);
+        prefix.extend_from_slice(&[0x72, 0x02, 0xcc, 0xdd, 0xfd, 0xe8, 0x03]);
+        prefix
+    }
+
+    #[test]
+    fn from_locking_script() {
+        let body = hex::decode(HASH160).unwrap();
+        let p2sh32 = Address::from_redeem_script_p2sh32(&[0x51], Network::Main);
+        let addresses = [
+            Address::new(body.clone(), Scheme::CashAddr, HashType::Key, Network::Main),
+            Address::new(body, Scheme::CashAddr, HashType::Script, Network::Main),
+            p2sh32,
+        ];
+        for address in addresses.iter() {
+            let script = address.to_locking_script().unwrap();
+            assert_eq!(
+                Address::from_locking_script(&script, Network::Main),
+                Ok(address.clone())
+            );
+
+            // The token prefix is stripped, leaving the token-aware address
+            let token_script = [token_prefix(), script].concat();
+            assert_eq!(
+                Address::from_locking_script(&token_script, Network::Main),
+                Ok(address.clone().into_token_aware())
+            );
+        }
+    }
+
+    #[test]
+    fn no_address() {
+        let key = hex::decode("0339a36013301597daef41fbe593a02cc513d0b55527ec2df1050e2e8ff49c85c2")
+            .unwrap();
+        let p2pk = [&[33], &key[..], &[OP_CHECKSIG]].concat();
+        let multisig = [
+            &[OP_1, 33],
+            &key[..],
+            &[33],
+            &key[..],
+            &[0x52, OP_CHECKMULTISIG],
+        ]
+        .concat();
+        let cases: [(&[u8], NoAddress); 9] = [
+            (&[OP_RETURN], NoAddress::NullData),
+            (&[OP_RETURN, 2, 0xab, 0xcd, OP_1, OP_0], NoAddress::NullData),
+            (&[OP_RETURN, OP_DUP], NoAddress::NonStandard),
+            (&p2pk, NoAddress::PublicKey),
+            (&multisig, NoAddress::Multisig),
+            (&multisig[1..], NoAddress::NonStandard),
+            (&[], NoAddress::NonStandard),
+            (&[OP_PUSHDATA1, 5, 0], NoAddress::NonStandard),
+            (&[PREFIX_TOKEN, 0], NoAddress::InvalidTokenPrefix),
+        ];
+        for (script, reason) in cases.iter() {
+            assert_eq!(
+                Address::from_locking_script(script, Network::Main),
+                Err(reason.clone())
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_token_prefixes() {
+        let p2pkh = hex::decode(["76a914", HASH160, "88ac"].concat()).unwrap();
+        let bitfields: [&[u8]; 7] = [
+            // Reserved bit
+            &[0x90, 0x01],
+            // No NFT or amount
+            &[0x00],
+            // Capability without an NFT
+            &[0x11, 0x01],
+            // Invalid capability
+            &[0x23],
+            // Empty commitment
+            &[0x60, 0x00],
+            // Zero amount
+            &[0x10, 0x00],
+            // Non-minimal amount
+            &[0x10, 0xfd, 0x01, 0x00],
+        ];
+        for bitfield in bitfields.iter() {
+            let mut script = vec![PREFIX_TOKEN];
+            script.extend_from_slice(&[0xaa; 32]);
+            script.extend_from_slice(bitfield);
+            script.extend_from_slice(&p2pkh);
+            assert_eq!(
+                Address::from_locking_script(&script, Network::Main),
+                Err(NoAddress::InvalidTokenPrefix)
+            );
+        }
+    }
+
+    #[test]
+    fn script_kind() {
+        let key = hex::decode("0339a36013301597daef41fbe593a02cc513d0b55527ec2df1050e2e8ff49c85c2")
+            .unwrap();
+        let key_address = Address::from_pubkey(&key, Network::Main).unwrap();
+
+        let p2pkh = key_address.to_locking_script().unwrap();
+        assert_eq!(
+            ScriptKind::from_locking_script(&p2pkh, Network::Main),
+            ScriptKind::P2pkh(key_address.clone())
+        );
+        let p2sh20 = Address::from_redeem_script(&p2pkh, Network::Test);
+        let kind = ScriptKind::from_locking_script(
+            &[token_prefix(), p2sh20.to_locking_script().unwrap()].concat(),
+            Network::Test,
+        );
+        assert_eq!(kind, ScriptKind::P2sh20(p2sh20.into_token_aware()));
+        let p2sh32 = Address::from_redeem_script_p2sh32(&p2pkh, Network::Main);
+        let kind =
+            ScriptKind::from_locking_script(&p2sh32.to_locking_script().unwrap(), Network::Main);
+        assert_eq!(kind.address(), Some(&p2sh32));
+
+        let p2pk = [&[33], &key[..], &[OP_CHECKSIG]].concat();
+        let kind = ScriptKind::from_locking_script(&p2pk, Network::Main);
+        assert_eq!(kind.address(), Some(&key_address));
+        assert_eq!(
+            kind,
+            ScriptKind::P2pk {
+                pubkey: key.clone(),
+                address: key_address,
+            }
+        );
+
+        let multisig = [
+            &[OP_1, 33],
+            &key[..],
+            &[33],
+            &key[..],
+            &[0x52, OP_CHECKMULTISIG],
+        ]
+        .concat();
+        assert_eq!(
+            ScriptKind::from_locking_script(&multisig, Network::Main),
+            ScriptKind::Multisig {
+                required: 1,
+                pubkeys: vec![key.clone(), key],
+            }
+        );
+
+        let null_data = [OP_RETURN, 2, 0xab, 0xcd, OP_0, OP_16, OP_1NEGATE];
+        assert_eq!(
+            ScriptKind::from_locking_script(&null_data, Network::Main),
+            ScriptKind::NullData(vec![vec![0xab, 0xcd], vec![], vec![16], vec![0x81]])
+        );
+        assert_eq!(
+            ScriptKind::from_locking_script(&[PREFIX_TOKEN], Network::Main),
+            ScriptKind::NonStandard
+        );
+        assert_eq!(ScriptKind::NonStandard.address(), None);
+    }
+}
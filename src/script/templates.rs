@@ -0,0 +1,318 @@
+use alloc::vec::Vec;
+
+use super::*;
+
+// Opcodes used by the templates
+const OP_IF: u8 = 0x63;
+const OP_ELSE: u8 = 0x67;
+const OP_ENDIF: u8 = 0x68;
+const OP_DROP: u8 = 0x75;
+const OP_SHA256: u8 = 0xa8;
+const OP_CHECKLOCKTIMEVERIFY: u8 = 0xb1;
+const OP_CHECKSEQUENCEVERIFY: u8 = 0xb2;
+
+// Sequence flag which disables relative lock times
+const SEQUENCE_DISABLE_FLAG: u32 = 1 << 31;
+
+/// Hash used to build the P2SH address of a redeem script.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Hash)]
+pub enum P2shType {
+    /// 20-byte Hash160 of the script.
+    P2sh20,
+    /// 32-byte double SHA-256 of the script.
+    P2sh32,
+}
+
+/// Redeem script built from a template, with the P2SH address paying to it.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Contract {
+    /// Serialized redeem script
+    pub redeem_script: Vec<u8>,
+    /// CashAddr P2SH address of the redeem script
+    pub address: Address,
+}
+
+impl Contract {
+    /// Wrap a redeem script, computing its address.
+    pub fn new(redeem_script: Vec<u8>, p2sh_type: P2shType, network: Network) -> Self {
+        let address = match p2sh_type {
+            P2shType::P2sh20 => Address::from_redeem_script(&redeem_script, network),
+            P2shType::P2sh32 => Address::from_redeem_script_p2sh32(&redeem_script, network),
+        };
+        Contract {
+            redeem_script,
+            address,
+        }
+    }
+
+    /// Build a hash-time-locked contract, spendable by `recipient` with the preimage of
+    /// the SHA-256 `secret_hash`, or by `refund` once the absolute `lock_time` has passed.
+    ///
+    /// The recipient unlocks with `<sig> <preimage> OP_1` and the refund with `<sig> OP_0`.
+    pub fn htlc(
+        secret_hash: &[u8; 32],
+        recipient: &[u8],
+        refund: &[u8],
+        lock_time: u32,
+        p2sh_type: P2shType,
+        network: Network,
+    ) -> Result<Self, TemplateError> {
+        check_pubkey(recipient)?;
+        check_pubkey(refund)?;
+
+        // OP_IF OP_SHA256 <hash> OP_EQUALVERIFY <recipient>
+        // OP_ELSE <lock time> OP_CHECKLOCKTIMEVERIFY OP_DROP <refund>
+        // OP_ENDIF OP_CHECKSIG
+        let mut script = vec![OP_IF, OP_SHA256];
+        push_data(&mut script, secret_hash);
+        script.push(OP_EQUALVERIFY);
+        push_data(&mut script, recipient);
+        script.push(OP_ELSE);
+        push_int(&mut script, lock_time);
+        script.extend_from_slice(&[OP_CHECKLOCKTIMEVERIFY, OP_DROP]);
+        push_data(&mut script, refund);
+        script.extend_from_slice(&[OP_ENDIF, OP_CHECKSIG]);
+        Ok(Contract::new(script, p2sh_type, network))
+    }
+
+    /// Build a vault spendable by `pubkey` once the absolute `lock_time`, a block height
+    /// below 500,000,000 or a UNIX timestamp otherwise, has passed.
+    pub fn timelock(
+        pubkey: &[u8],
+        lock_time: u32,
+        p2sh_type: P2shType,
+        network: Network,
+    ) -> Result<Self, TemplateError> {
+        locked_to_key(
+            pubkey,
+            lock_time,
+            OP_CHECKLOCKTIMEVERIFY,
+            p2sh_type,
+            network,
+        )
+    }
+
+    /// Build a vault spendable by `pubkey` once the relative lock time encoded in
+    /// `sequence`, as in BIP68, has passed since the funding output confirmed.
+    pub fn relative_timelock(
+        pubkey: &[u8],
+        sequence: u32,
+        p2sh_type: P2shType,
+        network: Network,
+    ) -> Result<Self, TemplateError> {
+        if sequence & SEQUENCE_DISABLE_FLAG != 0 {
+            return Err(TemplateError::DisabledSequence(sequence));
+        }
+        locked_to_key(pubkey, sequence, OP_CHECKSEQUENCEVERIFY, p2sh_type, network)
+    }
+}
+
+// Build <lock> <opcode> OP_DROP <pubkey> OP_CHECKSIG.
+fn locked_to_key(
+    pubkey: &[u8],
+    lock: u32,
+    opcode: u8,
+    p2sh_type: P2shType,
+    network: Network,
+) -> Result<Contract, TemplateError> {
+    check_pubkey(pubkey)?;
+    let mut script = Vec::new();
+    push_int(&mut script, lock);
+    script.extend_from_slice(&[opcode, OP_DROP]);
+    push_data(&mut script, pubkey);
+    script.push(OP_CHECKSIG);
+    Ok(Contract::new(script, p2sh_type, network))
+}
+
+// Append the minimal push of `data`.
+fn push_data(script: &mut Vec<u8>, data: &[u8]) {
+    match data {
+        [] => script.push(OP_0),
+        [n @ 1..=16] => script.push(OP_1 + n - 1),
+        [0x81] => script.push(OP_1NEGATE),
+        _ => {
+            let len = data.len();
+            if len < OP_PUSHDATA1 as usize {
+                script.push(len as u8);
+            } else if len <= 0xff {
+                script.extend_from_slice(&[OP_PUSHDATA1, len as u8]);
+            } else if len <= 0xffff {
+                script.push(OP_PUSHDATA2);
+                script.extend_from_slice(&(len as u16).to_le_bytes());
+            } else {
+                script.push(OP_PUSHDATA4);
+                script.extend_from_slice(&(len as u32).to_le_bytes());
+            }
+            script.extend_from_slice(data);
+        }
+    }
+}
+
+// Append the minimal push of a non-negative script number.
+fn push_int(script: &mut Vec<u8>, value: u32) {
+    // Little endian without trailing zeros, plus a zero byte if the sign bit is set
+    let mut bytes: Vec<u8> = value.to_le_bytes().to_vec();
+    while bytes.last() == Some(&0) {
+        bytes.pop();
+    }
+    if matches!(bytes.last(), Some(byte) if byte & 0x80 != 0) {
+        bytes.push(0);
+    }
+    push_data(script, &bytes);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PublicKeyError;
+    use alloc::vec;
+
+    const RECIPIENT: &str = "0339a36013301597daef41fbe593a02cc513d0b55527ec2df1050e2e8ff49c85c2";
+    const REFUND: &str = "02c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5";
+
+    fn pushed_int(value: u32) -> Vec<u8> {
+        let mut script = Vec::new();
+        push_int(&mut script, value);
+        script
+    }
+
+    #[test]
+    fn minimal_pushes() {
+        assert_eq!(pushed_int(0), vec![OP_0]);
+        assert_eq!(pushed_int(1), vec![OP_1]);
+        assert_eq!(pushed_int(16), vec![OP_16]);
+        assert_eq!(pushed_int(17), vec![1, 17]);
+        assert_eq!(pushed_int(0x7f), vec![1, 0x7f]);
+        assert_eq!(pushed_int(0x80), vec![2, 0x80, 0]);
+        assert_eq!(pushed_int(0x100), vec![2, 0, 1]);
+        assert_eq!(pushed_int(500_000_000), vec![4, 0x00, 0x65, 0xcd, 0x1d]);
+        assert_eq!(pushed_int(u32::MAX), vec![5, 0xff, 0xff, 0xff, 0xff, 0x00]);
+
+        for (len, prefix) in &[
+            (75, vec![75]),
+            (76, vec![OP_PUSHDATA1, 76]),
+            (256, vec![OP_PUSHDATA2, 0, 1]),
+            (0x1_0000, vec![OP_PUSHDATA4, 0, 0, 1, 0]),
+        ] {
+            let data = vec![0x42; *len];
+            let mut script = Vec::new();
+            push_data(&mut script, &data);
+            assert_eq!(script, [&prefix[..], &data].concat());
+            assert_eq!(instructions(&script), Some(vec![Instruction::Push(&data)]));
+        }
+        let mut script = Vec::new();
+        push_data(&mut script, &[0x81]);
+        assert_eq!(script, vec![OP_1NEGATE]);
+    }
+
+    #[test]
+    fn htlc() {
+        let recipient = hex::decode(RECIPIENT).unwrap();
+        let refund = hex::decode(REFUND).unwrap();
+        let secret_hash = [0x5a; 32];
+        let contract = Contract::htlc(
+            &secret_hash,
+            &recipient,
+            &refund,
+            700_000,
+            P2shType::P2sh20,
+            Network::Main,
+        )
+        .unwrap();
+
+        let expected = [
+            &[OP_IF, OP_SHA256, 32][..],
+            &secret_hash,
+            &[OP_EQUALVERIFY, 33],
+            &recipient,
+            &[
+                OP_ELSE,
+                3,
+                0x60,
+                0xae,
+                0x0a,
+                OP_CHECKLOCKTIMEVERIFY,
+                OP_DROP,
+                33,
+            ],
+            &refund,
+            &[OP_ENDIF, OP_CHECKSIG],
+        ]
+        .concat();
+        assert_eq!(contract.redeem_script, expected);
+        assert_eq!(
+            contract.address,
+            Address::from_redeem_script(&expected, Network::Main)
+        );
+        assert_eq!(
+            ScriptKind::from_locking_script(
+                &contract.address.to_locking_script().unwrap(),
+                Network::Main
+            ),
+            ScriptKind::P2sh20(contract.address.clone())
+        );
+
+        assert_eq!(
+            Contract::htlc(
+                &secret_hash,
+                &recipient,
+                &refund[1..],
+                700_000,
+                P2shType::P2sh20,
+                Network::Main,
+            ),
+            Err(TemplateError::PublicKey(PublicKeyError::InvalidLength(32)))
+        );
+    }
+
+    #[test]
+    fn timelocks() {
+        let pubkey = hex::decode(RECIPIENT).unwrap();
+        let contract =
+            Contract::timelock(&pubkey, 1_700_000_000, P2shType::P2sh32, Network::Test).unwrap();
+        let expected = [
+            &[
+                4,
+                0x00,
+                0xf1,
+                0x53,
+                0x65,
+                OP_CHECKLOCKTIMEVERIFY,
+                OP_DROP,
+                33,
+            ][..],
+            &pubkey,
+            &[OP_CHECKSIG],
+        ]
+        .concat();
+        assert_eq!(contract.redeem_script, expected);
+        assert!(contract.address.is_p2sh32());
+        assert_eq!(
+            contract.address,
+            Address::from_redeem_script_p2sh32(&expected, Network::Test)
+        );
+
+        // 10 blocks
+        let contract =
+            Contract::relative_timelock(&pubkey, 10, P2shType::P2sh20, Network::Main).unwrap();
+        let expected = [
+            &[OP_1 + 9, OP_CHECKSEQUENCEVERIFY, OP_DROP, 33][..],
+            &pubkey,
+            &[OP_CHECKSIG],
+        ]
+        .concat();
+        assert_eq!(contract.redeem_script, expected);
+        assert!(contract.address.is_p2sh20());
+
+        assert_eq!(
+            Contract::relative_timelock(&pubkey, 1 << 31, P2shType::P2sh20, Network::Main),
+            Err(TemplateError::DisabledSequence(1 << 31))
+        );
+        assert_eq!(
+            Contract::timelock(&[0x05; 33], 0, P2shType::P2sh20, Network::Main),
+            Err(TemplateError::PublicKey(PublicKeyError::InvalidPrefix(
+                0x05
+            )))
+        );
+    }
+}
@@ -0,0 +1,42 @@
+use core::fmt;
+#[cfg(feature = "std")]
+use std::error::Error;
+
+/// Error concerning decoding of transactions.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum DecodingError {
+    /// Hex string has an odd length or a non-hex character.
+    InvalidHex,
+    /// Data ended before the transaction was complete.
+    UnexpectedEnd,
+    /// Length prefix is truncated or not minimally encoded.
+    InvalidCompactSize,
+    /// Data continues after the transaction (length).
+    TrailingData(usize),
+}
+
+impl fmt::Display for DecodingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DecodingError::InvalidHex => write!(f, "invalid hex"),
+            DecodingError::UnexpectedEnd => write!(f, "unexpected end of data"),
+            DecodingError::InvalidCompactSize => write!(f, "invalid compact size"),
+            DecodingError::TrailingData(length) => write!(f, "trailing data ({})", length),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Error for DecodingError {
+    fn cause(&self) -> Option<&dyn Error> {
+        None
+    }
+    fn description(&self) -> &str {
+        match *self {
+            DecodingError::InvalidHex => "invalid hex",
+            DecodingError::UnexpectedEnd => "unexpected end of data",
+            DecodingError::InvalidCompactSize => "invalid compact size",
+            DecodingError::TrailingData(_) => "trailing data",
+        }
+    }
+}
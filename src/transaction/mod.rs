@@ -0,0 +1,328 @@
+pub mod errors;
+
+use alloc::vec::Vec;
+
+use crate::{
+    check_pubkey,
+    script::{instructions, read_compact_size, read_le, Instruction, NoAddress, ScriptKind},
+    Address, Network,
+};
+pub use errors::DecodingError;
+
+/// Transaction input.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct TxInput {
+    /// Hash of the transaction being spent, in serialized byte order
+    pub prev_txid: [u8; 32],
+    /// Index of the output being spent
+    pub prev_index: u32,
+    /// Unlocking script
+    pub script_sig: Vec<u8>,
+    /// Sequence number
+    pub sequence: u32,
+}
+
+impl TxInput {
+    /// Whether the input is the coinbase of a block, which spends no output and whose
+    /// unlocking script is chosen by the miner.
+    pub fn is_coinbase(&self) -> bool {
+        self.prev_txid == [0; 32] && self.prev_index == 0xffff_ffff
+    }
+
+    /// The address spent from, if the unlocking script is a signature followed by a public
+    /// key as spends of P2PKH outputs are. Coinbase inputs have no address.
+    pub fn p2pkh_address(&self, network: Network) -> Option<Address> {
+        if self.is_coinbase() {
+            return None;
+        }
+        match instructions(&self.script_sig)?.as_slice() {
+            [Instruction::Push(sig), Instruction::Push(pubkey)]
+                if !sig.is_empty() && check_pubkey(pubkey).is_ok() =>
+            {
+                Address::from_pubkey(pubkey, network).ok()
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Transaction output.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct TxOutput {
+    /// Value in satoshis
+    pub value: u64,
+    /// Locking script, including any CashTokens prefix
+    pub locking_script: Vec<u8>,
+}
+
+impl TxOutput {
+    /// The address paid to, token-aware if the output carries tokens.
+    pub fn address(&self, network: Network) -> Result<Address, NoAddress> {
+        Address::from_locking_script(&self.locking_script, network)
+    }
+
+    /// Classify the locking script.
+    pub fn kind(&self, network: Network) -> ScriptKind {
+        ScriptKind::from_locking_script(&self.locking_script, network)
+    }
+}
+
+/// Serialized transaction, parsed only far enough to list its inputs and outputs.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Transaction {
+    /// Version
+    pub version: u32,
+    /// Inputs
+    pub inputs: Vec<TxInput>,
+    /// Outputs
+    pub outputs: Vec<TxOutput>,
+    /// Lock time
+    pub lock_time: u32,
+}
+
+impl Transaction {
+    /// Attempt to parse a serialized transaction.
+    pub fn decode(mut data: &[u8]) -> Result<Self, DecodingError> {
+        let data = &mut data;
+        let version = read_u32(data)?;
+
+        let mut inputs = Vec::new();
+        for _ in 0..read_count(data)? {
+            let mut prev_txid = [0u8; 32];
+            prev_txid.copy_from_slice(read_bytes(data, 32)?);
+            inputs.push(TxInput {
+                prev_txid,
+                prev_index: read_u32(data)?,
+                script_sig: read_script(data)?,
+                sequence: read_u32(data)?,
+            });
+        }
+
+        let mut outputs = Vec::new();
+        for _ in 0..read_count(data)? {
+            outputs.push(TxOutput {
+                value: read_le(data, 8).ok_or(DecodingError::UnexpectedEnd)?,
+                locking_script: read_script(data)?,
+            });
+        }
+
+        let lock_time = read_u32(data)?;
+        if !data.is_empty() {
+            return Err(DecodingError::TrailingData(data.len()));
+        }
+        Ok(Transaction {
+            version,
+            inputs,
+            outputs,
+            lock_time,
+        })
+    }
+
+    /// Attempt to parse a hex encoded serialized transaction.
+    pub fn from_hex(hex: &str) -> Result<Self, DecodingError> {
+        let pairs = hex.as_bytes().chunks_exact(2);
+        if !pairs.remainder().is_empty() {
+            return Err(DecodingError::InvalidHex);
+        }
+        let nibble = |c: u8| match c {
+            b'0'..=b'9' => Ok(c - b'0'),
+            b'a'..=b'f' => Ok(c - b'a' + 10),
+            b'A'..=b'F' => Ok(c - b'A' + 10),
+            _ => Err(DecodingError::InvalidHex),
+        };
+        let data = pairs
+            .map(|pair| Ok((nibble(pair[0])? << 4) | nibble(pair[1])?))
+            .collect::<Result<Vec<u8>, DecodingError>>()?;
+        Self::decode(&data)
+    }
+
+    /// Iterate over the value of each output and the address it pays to.
+    pub fn output_addresses(
+        &self,
+        network: Network,
+    ) -> impl Iterator<Item = (u64, Result<Address, NoAddress>)> + '_ {
+        self.outputs
+            .iter()
+            .map(move |output| (output.value, output.address(network.clone())))
+    }
+
+    /// Iterate over the addresses spent from by P2PKH inputs, skipping the coinbase.
+    pub fn input_addresses(&self, network: Network) -> impl Iterator<Item = Address> + '_ {
+        self.inputs
+            .iter()
+            .filter_map(move |input| input.p2pkh_address(network.clone()))
+    }
+}
+
+// Read `len` bytes, advancing `data`.
+fn read_bytes<'a>(data: &mut &'a [u8], len: usize) -> Result<&'a [u8], DecodingError> {
+    if data.len() < len {
+        return Err(DecodingError::UnexpectedEnd);
+    }
+    let (bytes, rest) = data.split_at(len);
+    *data = rest;
+    Ok(bytes)
+}
+
+// Read a little endian 32-bit integer, advancing `data`.
+fn read_u32(data: &mut &[u8]) -> Result<u32, DecodingError> {
+    read_le(data, 4)
+        .map(|value| value as u32)
+        .ok_or(DecodingError::UnexpectedEnd)
+}
+
+// Read a CompactSize count or length, advancing `data`.
+fn read_count(data: &mut &[u8]) -> Result<u64, DecodingError> {
+    read_compact_size(data).ok_or(DecodingError::InvalidCompactSize)
+}
+
+// Read a length prefixed script, advancing `data`.
+fn read_script(data: &mut &[u8]) -> Result<Vec<u8>, DecodingError> {
+    let len = read_count(data)?;
+    if len > data.len() as u64 {
+        return Err(DecodingError::UnexpectedEnd);
+    }
+    Ok(read_bytes(data, len as usize)?.to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{HashType, Scheme};
+    use alloc::vec;
+
+    // The coinbase transaction of the genesis block, paying to a public key
+    const GENESIS_COINBASE: &str = "01000000010000000000000000000000000000000000000000000000000000000000000000ffffffff4d04ffff001d0104455468652054696d65732030332f4a616e2f32303039204368616e63656c6c6f72206f6e206272696e6b206f66207365636f6e64206261696c6f757420666f722062616e6b73ffffffff0100f2052a01000000434104678afdb0fe5548271967f1a67130b7105cd6a828e03909a67962e0ea1f61deb649f6bc3f4cef38c4f35504e51ec112de5c384df7ba0b8d578a4c702b6bf11d5fac00000000";
+
+    const PUBKEY: &str = "0339a36013301597daef41fbe593a02cc513d0b55527ec2df1050e2e8ff49c85c2";
+
+    #[test]
+    fn genesis_coinbase() {
+        let tx = Transaction::from_hex(GENESIS_COINBASE).unwrap();
+        assert_eq!(tx.version, 1);
+        assert_eq!(tx.inputs.len(), 1);
+        assert_eq!(tx.inputs[0].prev_index, 0xffff_ffff);
+        assert_eq!(tx.lock_time, 0);
+
+        let outputs: Vec<_> = tx.output_addresses(Network::Main).collect();
+        assert_eq!(outputs, vec![(5_000_000_000, Err(NoAddress::PublicKey))]);
+        let mut address = tx.outputs[0].kind(Network::Main).address().unwrap().clone();
+        address.scheme = Scheme::Base58;
+        assert_eq!(
+            address.encode().unwrap(),
+            "1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa"
+        );
+        assert!(tx.inputs[0].is_coinbase());
+        assert_eq!(tx.input_addresses(Network::Main).count(), 0);
+    }
+
+    #[test]
+    fn coinbase_input() {
+        // A coinbase whose unlocking script resembles a P2PKH spend
+        let pubkey = hex::decode(PUBKEY).unwrap();
+        let mut input = TxInput {
+            prev_txid: [0; 32],
+            prev_index: 0xffff_ffff,
+            script_sig: [&[3, 0x01, 0x02, 0x03, 33][..], &pubkey].concat(),
+            sequence: 0xffff_ffff,
+        };
+        assert!(input.is_coinbase());
+        assert_eq!(input.p2pkh_address(Network::Main), None);
+
+        input.prev_index = 0;
+        assert!(!input.is_coinbase());
+        assert_eq!(
+            input.p2pkh_address(Network::Main),
+            Address::from_pubkey(&pubkey, Network::Main).ok()
+        );
+    }
+
+    #[test]
+    fn p2pkh_spend() {
+        let pubkey = hex::decode(PUBKEY).unwrap();
+        let spender = Address::from_pubkey(&pubkey, Network::Main).unwrap();
+        let payee = Address::from_redeem_script(&[0x51], Network::Main);
+
+        // Version, one input signed with a dummy signature, two outputs and lock time
+        let mut data = vec![2, 0, 0, 0, 1];
+        data.extend_from_slice(&[0x11; 32]);
+        data.extend_from_slice(&[1, 0, 0, 0, 1 + 71 + 1 + 33, 71]);
+        data.extend_from_slice(&[0x30; 71]);
+        data.push(33);
+        data.extend_from_slice(&pubkey);
+        data.extend_from_slice(&[0xfe, 0xff, 0xff, 0xff, 2]);
+        let token_script = [
+            &[0xef][..],
+            &[0xaa; 32],
+            &[0x10, 0x01],
+            &payee.to_locking_script().unwrap(),
+        ]
+        .concat();
+        data.extend_from_slice(&1000u64.to_le_bytes());
+        data.push(token_script.len() as u8);
+        data.extend_from_slice(&token_script);
+        data.extend_from_slice(&0u64.to_le_bytes());
+        data.extend_from_slice(&[3, 0x6a, 1, 0x42]);
+        data.extend_from_slice(&[0x40, 0x42, 0x0f, 0x00]);
+
+        let tx = Transaction::decode(&data).unwrap();
+        assert_eq!(tx.version, 2);
+        assert_eq!(tx.inputs[0].prev_txid, [0x11; 32]);
+        assert_eq!(tx.inputs[0].prev_index, 1);
+        assert_eq!(tx.inputs[0].sequence, 0xffff_fffe);
+        assert_eq!(tx.lock_time, 1_000_000);
+        assert_eq!(
+            tx.input_addresses(Network::Main).collect::<Vec<_>>(),
+            vec![spender]
+        );
+
+        let outputs: Vec<_> = tx.output_addresses(Network::Main).collect();
+        assert_eq!(
+            outputs,
+            vec![
+                (1000, Ok(payee.into_token_aware())),
+                (0, Err(NoAddress::NullData))
+            ]
+        );
+        assert_eq!(
+            tx.outputs[1].kind(Network::Main),
+            ScriptKind::NullData(vec![vec![0x42]])
+        );
+        assert_eq!(
+            tx.outputs[0]
+                .address(Network::Test)
+                .map(|address| address.hash_type),
+            Ok(HashType::TokenScript)
+        );
+
+        // Every truncation fails without panicking
+        for len in 0..data.len() {
+            assert!(Transaction::decode(&data[..len]).is_err());
+        }
+        data.push(0);
+        assert_eq!(
+            Transaction::decode(&data),
+            Err(DecodingError::TrailingData(1))
+        );
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(Transaction::from_hex("0"), Err(DecodingError::InvalidHex));
+        assert_eq!(Transaction::from_hex("zz"), Err(DecodingError::InvalidHex));
+        assert_eq!(
+            Transaction::from_hex("01000000"),
+            Err(DecodingError::InvalidCompactSize)
+        );
+        // Non-minimal input count
+        assert_eq!(
+            Transaction::from_hex("01000000fd0100"),
+            Err(DecodingError::InvalidCompactSize)
+        );
+        // Huge input count with no data
+        assert_eq!(
+            Transaction::from_hex("01000000ffffffffffffffffff"),
+            Err(DecodingError::UnexpectedEnd)
+        );
+    }
+}
@@ -0,0 +1,92 @@
+use core::fmt;
+#[cfg(feature = "std")]
+use std::error::Error;
+
+use crate::{base58::Base58Error, Network};
+
+/// Error concerning encoding of WIF private keys.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum EncodingError {
+    /// Network has no WIF version byte (network).
+    UnsupportedNetwork(Network),
+    /// Secret key is zero or not below the curve order.
+    InvalidSecret,
+}
+
+impl fmt::Display for EncodingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EncodingError::UnsupportedNetwork(network) => {
+                write!(f, "unsupported network ({:?})", network)
+            }
+            EncodingError::InvalidSecret => write!(f, "invalid secret key"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Error for EncodingError {
+    fn cause(&self) -> Option<&dyn Error> {
+        None
+    }
+    fn description(&self) -> &str {
+        match *self {
+            EncodingError::UnsupportedNetwork(_) => "unsupported network",
+            EncodingError::InvalidSecret => "invalid secret key",
+        }
+    }
+}
+
+/// Error concerning decoding of WIF private keys.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum DecodingError {
+    /// Base58Check decoding failed.
+    Base58(Base58Error),
+    /// Invalid length (length).
+    InvalidLength(usize),
+    /// Version byte was not recognized.
+    InvalidVersion(u8),
+    /// Secret key is zero or not below the curve order.
+    InvalidSecret,
+    /// Compression flag was not 0x01 (flag).
+    InvalidCompressionFlag(u8),
+}
+
+impl From<Base58Error> for DecodingError {
+    fn from(err: Base58Error) -> Self {
+        DecodingError::Base58(err)
+    }
+}
+
+impl fmt::Display for DecodingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DecodingError::Base58(err) => write!(f, "base58 decoding failed: {}", err),
+            DecodingError::InvalidLength(length) => write!(f, "invalid length ({})", length),
+            DecodingError::InvalidVersion(v) => write!(f, "invalid version byte ({})", v),
+            DecodingError::InvalidCompressionFlag(flag) => {
+                write!(f, "invalid compression flag ({})", flag)
+            }
+            DecodingError::InvalidSecret => write!(f, "invalid secret key"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Error for DecodingError {
+    fn cause(&self) -> Option<&dyn Error> {
+        match self {
+            DecodingError::Base58(err) => Some(err),
+            _ => None,
+        }
+    }
+    fn description(&self) -> &str {
+        match *self {
+            DecodingError::Base58(_) => "base58 decoding failed",
+            DecodingError::InvalidLength(_) => "invalid length",
+            DecodingError::InvalidVersion(_) => "invalid version",
+            DecodingError::InvalidCompressionFlag(_) => "invalid compression flag",
+            DecodingError::InvalidSecret => "invalid secret key",
+        }
+    }
+}
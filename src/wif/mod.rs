@@ -0,0 +1,289 @@
+pub mod errors;
+
+use alloc::{string::String, vec::Vec};
+use core::fmt;
+
+use zeroize::{Zeroize, Zeroizing};
+
+#[cfg(feature = "secp256k1")]
+use secp256k1::{PublicKey, Secp256k1, SecretKey};
+
+use crate::{base58, constant_time_eq, is_valid_secret, Network};
+#[cfg(feature = "secp256k1")]
+use crate::{bip32::pubkey_address, Address};
+pub use errors::{DecodingError, EncodingError};
+
+// Version bytes
+const MAINNET_VERSION: u8 = 0x80;
+const TESTNET_VERSION: u8 = 0xef;
+
+// Length of a secret key
+const SECRET_LEN: usize = 32;
+
+// Maximum length of a WIF string, just over 38 * log_58(256)
+const MAX_WIF_LEN: usize = 52;
+
+// Suffix marking that the public key is compressed
+const COMPRESSED_FLAG: u8 = 0x01;
+
+/// Private key in Wallet Import Format.
+///
+/// The secret bytes are zeroized when dropped, and compared in constant time.
+#[derive(Clone)]
+pub struct Wif {
+    secret: [u8; SECRET_LEN],
+    compressed: bool,
+    network: Network,
+}
+
+impl Wif {
+    /// Create a new WIF private key. `compressed` indicates whether the corresponding public
+    /// key is serialized in compressed form.
+    pub fn new(secret: [u8; SECRET_LEN], compressed: bool, network: Network) -> Self {
+        Wif {
+            secret,
+            compressed,
+            network,
+        }
+    }
+
+    /// Borrow the secret key bytes.
+    pub fn secret_bytes(&self) -> &[u8; SECRET_LEN] {
+        &self.secret
+    }
+
+    /// Whether the corresponding public key is serialized in compressed form.
+    pub fn is_compressed(&self) -> bool {
+        self.compressed
+    }
+
+    /// Borrow the network. Regtest keys share the testnet version byte, so decode as
+    /// `Network::Test`.
+    pub fn network(&self) -> &Network {
+        &self.network
+    }
+
+    /// Attempt to convert the private key to a WIF string.
+    pub fn encode(&self) -> Result<String, EncodingError> {
+        let version = match self.network {
+            Network::Main => MAINNET_VERSION,
+            Network::Test | Network::Regtest => TESTNET_VERSION,
+            Network::Other(_) => {
+                return Err(EncodingError::UnsupportedNetwork(self.network.clone()))
+            }
+        };
+
+        let mut payload = Zeroizing::new(Vec::with_capacity(1 + SECRET_LEN + 1));
+        payload.push(version);
+        payload.extend_from_slice(&self.secret);
+        if self.compressed {
+            payload.push(COMPRESSED_FLAG);
+        }
+        Ok(base58::encode_check(&payload))
+    }
+
+    /// Attempt to convert a WIF string to a private key.
+    pub fn decode(wif_str: &str) -> Result<Self, DecodingError> {
+        // Reject overlong strings before the quadratic decoding
+        if wif_str.len() > MAX_WIF_LEN {
+            return Err(DecodingError::InvalidLength(wif_str.len()));
+        }
+        let payload = base58::decode_check_zeroizing(wif_str)?;
+
+        // Check length and compression flag
+        let length = payload.len();
+        let compressed = match length {
+            33 => false,
+            34 if payload[33] == COMPRESSED_FLAG => true,
+            34 => return Err(DecodingError::InvalidCompressionFlag(payload[33])),
+            _ => return Err(DecodingError::InvalidLength(length)),
+        };
+
+        // Parse network
+        let network = match payload[0] {
+            MAINNET_VERSION => Network::Main,
+            TESTNET_VERSION => Network::Test,
+            version => return Err(DecodingError::InvalidVersion(version)),
+        };
+
+        let wif = Wif {
+            secret: {
+                let mut secret = [0u8; SECRET_LEN];
+                secret.copy_from_slice(&payload[1..=SECRET_LEN]);
+                secret
+            },
+            compressed,
+            network,
+        };
+        if !is_valid_secret(&wif.secret) {
+            return Err(DecodingError::InvalidSecret);
+        }
+        Ok(wif)
+    }
+
+    /// Attempt to compute the P2PKH address of the public key, serialized in the form
+    /// selected by the compression flag.
+    #[cfg(feature = "secp256k1")]
+    pub fn to_address(&self) -> Result<Address, EncodingError> {
+        let mut secret =
+            SecretKey::from_slice(&self.secret).map_err(|_| EncodingError::InvalidSecret)?;
+        let key = PublicKey::from_secret_key(&Secp256k1::signing_only(), &secret);
+        secret.non_secure_erase();
+        let network = self.network.clone();
+        Ok(if self.compressed {
+            pubkey_address(&key.serialize(), network)
+        } else {
+            pubkey_address(&key.serialize_uncompressed(), network)
+        })
+    }
+}
+
+impl PartialEq for Wif {
+    fn eq(&self, other: &Self) -> bool {
+        // Compare every secret byte regardless of the other fields
+        let secrets_eq = constant_time_eq(&self.secret, &other.secret);
+        secrets_eq && self.compressed == other.compressed && self.network == other.network
+    }
+}
+
+impl Eq for Wif {}
+
+/// Omits the secret key bytes.
+impl fmt::Debug for Wif {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Wif")
+            .field("compressed", &self.compressed)
+            .field("network", &self.network)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for Wif {
+    fn drop(&mut self) {
+        self.secret.zeroize();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::{format, vec};
+
+    const SECRET: &str = "0c28fca386c7a227600b2fe50b7cae11ec86d3bf1fbe471be89827e19d72aa1d";
+
+    fn secret() -> [u8; SECRET_LEN] {
+        let mut secret = [0u8; SECRET_LEN];
+        secret.copy_from_slice(&hex::decode(SECRET).unwrap());
+        secret
+    }
+
+    #[test]
+    fn mainnet() {
+        let uncompressed = "5HueCGU8rMjxEXxiPuD5BDku4MkFqeZyd4dZ1jvhTVqvbTLvyTJ";
+        let wif = Wif::decode(uncompressed).unwrap();
+        assert_eq!(wif.secret_bytes(), &secret());
+        assert!(!wif.is_compressed());
+        assert_eq!(wif.network(), &Network::Main);
+        assert_eq!(wif.encode().unwrap(), uncompressed);
+
+        let compressed = "KwdMAjGmerYanjeui5SHS7JkmpZvVipYvB2LJGU1ZxJwYvP98617";
+        let wif = Wif::decode(compressed).unwrap();
+        assert_eq!(wif.secret_bytes(), &secret());
+        assert!(wif.is_compressed());
+        assert_eq!(wif.encode().unwrap(), compressed);
+    }
+
+    #[test]
+    fn testnet() {
+        for compressed in &[false, true] {
+            let wif = Wif::new(secret(), *compressed, Network::Regtest);
+            let wif_str = wif.encode().unwrap();
+            assert!(wif_str.starts_with(if *compressed { 'c' } else { '9' }));
+
+            let decoded = Wif::decode(&wif_str).unwrap();
+            assert_eq!(decoded, Wif::new(secret(), *compressed, Network::Test));
+            assert_ne!(decoded, Wif::new(secret(), !*compressed, Network::Test));
+            let mut other = secret();
+            other[31] ^= 1;
+            assert_ne!(decoded, Wif::new(other, *compressed, Network::Test));
+        }
+    }
+
+    #[test]
+    fn errors() {
+        let wif = Wif::new(secret(), true, Network::Other("ecash".into()));
+        assert_eq!(
+            wif.encode(),
+            Err(EncodingError::UnsupportedNetwork(Network::Other(
+                "ecash".into()
+            )))
+        );
+
+        let mut payload = vec![MAINNET_VERSION];
+        payload.extend_from_slice(&secret());
+        payload.push(0x02);
+        assert_eq!(
+            Wif::decode(&base58::encode_check(&payload)),
+            Err(DecodingError::InvalidCompressionFlag(0x02))
+        );
+        payload[0] = 0x00;
+        payload.pop();
+        assert_eq!(
+            Wif::decode(&base58::encode_check(&payload)),
+            Err(DecodingError::InvalidVersion(0x00))
+        );
+        assert_eq!(
+            Wif::decode(&base58::encode_check(&payload[..20])),
+            Err(DecodingError::InvalidLength(20))
+        );
+        assert!(matches!(
+            Wif::decode("5HueCGU8rMjxEXxiPuD5BDku4MkFqeZyd4dZ1jvhTVqvbTLvyTK"),
+            Err(DecodingError::Base58(_))
+        ));
+        assert_eq!(
+            Wif::decode(&"5".repeat(100_000)),
+            Err(DecodingError::InvalidLength(100_000))
+        );
+
+        // Secrets must be in the range [1, n)
+        for invalid in &[[0u8; SECRET_LEN], crate::CURVE_ORDER] {
+            let wif = Wif::new(*invalid, true, Network::Main);
+            assert_eq!(
+                Wif::decode(&wif.encode().unwrap()),
+                Err(DecodingError::InvalidSecret)
+            );
+        }
+    }
+
+    #[cfg(feature = "secp256k1")]
+    #[test]
+    fn to_address() {
+        let mut wif = Wif::new(secret(), false, Network::Main);
+        let mut address = wif.to_address().unwrap();
+        address.scheme = crate::Scheme::Base58;
+        assert_eq!(
+            address.encode().unwrap(),
+            "1GAehh7TsJAHuUAeKZcXf5CnwuGuGgyX2S"
+        );
+
+        wif.compressed = true;
+        let mut address = wif.to_address().unwrap();
+        address.scheme = crate::Scheme::Base58;
+        assert_eq!(
+            address.encode().unwrap(),
+            "1LoVGDgRs9hTfTNJNuXKSpywcbdvwRXpmK"
+        );
+
+        let wif = Wif::new([0; SECRET_LEN], true, Network::Main);
+        assert_eq!(wif.to_address(), Err(EncodingError::InvalidSecret));
+    }
+
+    #[test]
+    fn debug_omits_secret() {
+        let wif = Wif::new(secret(), true, Network::Main);
+        assert_eq!(
+            format!("{:?}", wif),
+            "Wif { compressed: true, network: Main, .. }"
+        );
+    }
+}